@@ -1,7 +1,7 @@
 use axum::http::StatusCode;
 use axum::{body::Body, http::Request, middleware::Next, response::IntoResponse};
 use problem_details::ProblemDetails;
-use source_downloader_sdk::component::ComponentError;
+use source_downloader_sdk::component::{ComponentError, ProcessingError};
 use std::{
     fmt,
     panic::{self, AssertUnwindSafe},
@@ -50,6 +50,12 @@ impl From<ComponentError> for AppError {
     }
 }
 
+impl From<ProcessingError> for AppError {
+    fn from(err: ProcessingError) -> Self {
+        Self::InternalError(err.message().to_string())
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> axum::response::Response {
         let (status_code, title, detail) = match &self {
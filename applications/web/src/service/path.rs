@@ -1,8 +1,8 @@
+use crate::error_handle::AppError;
 use crate::ApplicationContext;
 use axum::extract::State;
 use axum::routing::delete;
 use axum::{Json, Router};
-use source_downloader_core::application::CoreApplication;
 use std::sync::Arc;
 use tracing::info;
 
@@ -12,13 +12,18 @@ pub fn register_routers(ctx: Arc<ApplicationContext>) -> Router {
             "/target-path",
             Router::new().route("/", delete(delete_target_paths)),
         )
-        .with_state(ctx.core.clone())
+        .with_state(ctx)
 }
 
+/// 清理预占用的目标路径
 #[axum::debug_handler]
 async fn delete_target_paths(
-    State(_core): State<Arc<CoreApplication>>,
-    Json(_): Json<Vec<String>>,
-) -> () {
-    info!("delete_target_paths")
+    State(ctx): State<Arc<ApplicationContext>>,
+    Json(paths): Json<Vec<String>>,
+) -> Result<(), AppError> {
+    info!("delete_target_paths {:?}", paths);
+    ctx.storage
+        .delete_paths(&paths)
+        .await
+        .map_err(|e| AppError::InternalError(e.message))
 }
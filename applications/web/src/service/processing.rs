@@ -1,9 +1,9 @@
+use crate::error_handle::AppError;
 use crate::ApplicationContext;
 use axum::extract::{Path, Query, State};
 use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
 use serde::Deserialize;
-use source_downloader_core::application::CoreApplication;
 use source_downloader_sdk::storage::{ItemContentLite, ProcessingContent, ProcessingStatus};
 use source_downloader_sdk::time::{OffsetDateTime, UtcDateTime};
 use source_downloader_sdk::SourceItem;
@@ -21,12 +21,12 @@ pub fn register_routers(ctx: Arc<ApplicationContext>) -> Router {
                 .route("/{id}", delete(delete_content))
                 .route("/{id}/reprocess", post(reprocess)),
         )
-        .with_state(ctx.core.clone())
+        .with_state(ctx)
 }
 
 #[axum::debug_handler]
 async fn get_content(
-    State(_core): State<Arc<CoreApplication>>,
+    State(_ctx): State<Arc<ApplicationContext>>,
     Path(id): Path<i64>,
 ) -> Json<ProcessingContent> {
     info!("get_content id={}", id);
@@ -59,7 +59,7 @@ async fn get_content(
 
 #[axum::debug_handler]
 async fn query_contents(
-    State(_core): State<Arc<CoreApplication>>,
+    State(_ctx): State<Arc<ApplicationContext>>,
     Query(query): Query<QueryContents>,
 ) -> Json<Vec<ProcessingContent>> {
     info!(
@@ -71,7 +71,7 @@ async fn query_contents(
 
 #[axum::debug_handler]
 async fn update_content(
-    State(_core): State<Arc<CoreApplication>>,
+    State(_ctx): State<Arc<ApplicationContext>>,
     Path(id): Path<String>,
     Json(body): Json<UpdateContent>,
 ) -> () {
@@ -84,13 +84,30 @@ async fn update_content(
 }
 
 #[axum::debug_handler]
-async fn delete_content(State(_core): State<Arc<CoreApplication>>, Path(id): Path<String>) -> () {
+async fn delete_content(State(_ctx): State<Arc<ApplicationContext>>, Path(id): Path<String>) -> () {
     info!("delete_content id={}", id);
 }
 
 #[axum::debug_handler]
-async fn reprocess(State(_core): State<Arc<CoreApplication>>, Path(id): Path<String>) -> () {
+async fn reprocess(
+    State(ctx): State<Arc<ApplicationContext>>,
+    Path(id): Path<i64>,
+) -> Result<(), AppError> {
     info!("reprocess id={}", id);
+    let content = ctx
+        .storage
+        .find_content_by_id(id)
+        .await
+        .map_err(|e| AppError::InternalError(e.message))?
+        .ok_or_else(|| AppError::NotFound("Processing content not found".into()))?;
+    let p = ctx
+        .core
+        .processor_manager
+        .get_processor(&content.processor_name)
+        .and_then(|wp| wp.processor.clone())
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    p.reprocess(id).await?;
+    Ok(())
 }
 
 #[allow(dead_code)]
@@ -1,19 +1,30 @@
 use crate::error_handle::AppError;
 use crate::ApplicationContext;
+use axum::body::Body;
 use axum::extract::{Path, Query, State};
+use axum::http::header;
+use axum::response::sse::{Event, KeepAlive};
+use axum::response::{IntoResponse, Response, Sse};
 use axum::routing::{delete, get, post, put};
 use axum::{Json, Router};
+use futures_util::{Stream, stream};
 use serde::{Deserialize, Serialize};
 use serde_qs::to_string;
 use source_downloader_core::application::CoreApplication;
 use source_downloader_core::config::ProcessorConfig;
-use source_downloader_sdk::component::ProcessTask;
-use source_downloader_sdk::serde_json::{Map, Value};
-use source_downloader_sdk::time::UtcDateTime;
+use source_downloader_core::source_processor;
+use source_downloader_core::source_processor::DryRunResult;
+use source_downloader_sdk::component::ProcessingError;
+use source_downloader_sdk::serde_json::{self, json, Map, Value};
+use source_downloader_sdk::storage::{ProcessingStatus, ProcessorRun, ProcessorRunQuery};
+use source_downloader_sdk::time::{OffsetDateTime, UtcDateTime};
 use source_downloader_sdk::SourceItem;
 use std::collections::HashSet;
+use std::convert::Infallible;
 use std::sync::Arc;
-use tracing::info;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
 pub fn register_routers(ctx: Arc<ApplicationContext>) -> Router {
     Router::new()
@@ -27,6 +38,7 @@ pub fn register_routers(ctx: Arc<ApplicationContext>) -> Router {
                         .delete(delete_processor),
                 )
                 .route("/", get(query_processors).post(create_processor))
+                .route("/events", get(event_stream))
                 .route("/{name}/reload", post(reload_processor))
                 .route("/{name}/dry-run", get(dry_run).post(dry_run))
                 .route(
@@ -34,28 +46,52 @@ pub fn register_routers(ctx: Arc<ApplicationContext>) -> Router {
                     get(dry_run_stream).post(dry_run_stream),
                 )
                 .route("/{name}/trigger", post(trigger_processor))
+                .route("/{name}/cancel", post(cancel_processor))
                 .route("/{name}/rename", post(trigger_rename))
                 .route("/{name}/items", post(post_items))
-                .route("/{name}/state", get(get_state))
                 .route("/{name}/pointer", put(update_pointer))
                 .route("/{name}/contents", delete(delete_contents)),
         )
         .with_state(ctx.core.clone())
+        .merge(
+            Router::new()
+                .route("/processor/{name}/runs", get(query_runs))
+                .route("/processor/{name}/state", get(get_state))
+                .with_state(ctx),
+        )
 }
 
 #[axum::debug_handler]
-async fn get_processor(State(_): State<Arc<CoreApplication>>, Path(name): Path<String>) -> () {
+async fn get_processor(
+    State(core): State<Arc<CoreApplication>>,
+    Path(name): Path<String>,
+) -> Result<Json<ProcessorInfo>, AppError> {
     info!("get_processor name={}", name);
-    todo!()
+    let config = core
+        .config_operator
+        .get_processor_config(&name)
+        .ok_or_else(|| AppError::NotFound("Processor config not found".into()))?;
+    Ok(Json(ProcessorInfo::new(&core, config)))
 }
 
 #[axum::debug_handler]
 async fn query_processors(
-    State(_core): State<Arc<CoreApplication>>,
-    Query(_): Query<QueryParams>,
+    State(core): State<Arc<CoreApplication>>,
+    Query(params): Query<QueryParams>,
 ) -> Json<Vec<ProcessorInfo>> {
     info!("query_processors");
-    todo!()
+    let size = params.size.unwrap_or(20) as usize;
+    let page = params.page.unwrap_or(0) as usize;
+    let mut configs = core.config_operator.get_all_processor_config();
+    configs.sort_by(|a, b| a.name.cmp(&b.name));
+    let infos = configs
+        .into_iter()
+        .filter(|x| params.name.as_ref().is_none_or(|name| x.name.contains(name)))
+        .skip(page * size)
+        .take(size)
+        .map(|config| ProcessorInfo::new(&core, config))
+        .collect();
+    Json(infos)
 }
 
 #[axum::debug_handler]
@@ -123,7 +159,80 @@ async fn trigger_processor(
         .processor
         .clone()
         .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
-    let _ = p.run().await;
+    let _ = p.run_manually().await;
+    Ok(())
+}
+
+/// 实时推送处理事件, 指定name时只推送对应处理器的事件
+#[axum::debug_handler]
+async fn event_stream(
+    State(core): State<Arc<CoreApplication>>,
+    Query(params): Query<EventStreamParams>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    info!("event_stream name={:?}", params.name);
+    let receiver = core.processor_manager.event_bus().subscribe();
+    let events = stream::unfold(receiver, move |mut receiver| {
+        let name = params.name.clone();
+        async move {
+            loop {
+                let event = match receiver.recv().await {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("event_stream lagged, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return None,
+                };
+                if name.as_ref().is_some_and(|x| *x != event.processor_name) {
+                    continue;
+                }
+                let data = serde_json::to_string(&event).unwrap_or("{}".to_string());
+                let sse = Event::default().event("process-event").data(data);
+                return Some((Ok(sse), receiver));
+            }
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// 按id倒序分页, 下一页使用上一页最后一条的id作为maxId
+#[axum::debug_handler]
+async fn query_runs(
+    State(ctx): State<Arc<ApplicationContext>>,
+    Path(name): Path<String>,
+    Query(params): Query<QueryRunsParams>,
+) -> Result<Json<Vec<ProcessorRun>>, AppError> {
+    info!("query_runs name={}", name);
+    let runs = ctx
+        .storage
+        .query_processor_runs(&ProcessorRunQuery {
+            processor_name: name,
+            max_id: params.max_id,
+            limit: params.limit.unwrap_or(20),
+        })
+        .await
+        .map_err(|e| AppError::InternalError(e.message))?;
+    Ok(Json(runs))
+}
+
+#[axum::debug_handler]
+async fn cancel_processor(
+    State(_core): State<Arc<CoreApplication>>,
+    Path(_name): Path<String>,
+    Query(params): Query<CancelParams>,
+) -> Result<(), AppError> {
+    info!("cancel_processor name={}", _name);
+    let wp = _core
+        .processor_manager
+        .get_processor(&_name)
+        .ok_or_else(|| AppError::NotFound("Processor not found".into()))?;
+    let p = wp
+        .processor
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    if !p.cancel(params.cancel_submitted.unwrap_or(false)) {
+        return Err(AppError::BadRequest("Processor is not processing".into()));
+    }
     Ok(())
 }
 
@@ -131,10 +240,19 @@ async fn trigger_processor(
 async fn dry_run(
     State(_core): State<Arc<CoreApplication>>,
     Path(_name): Path<String>,
-    Json(_): Json<Option<DryRunOptions>>,
-) -> () {
+    Json(options): Json<Option<DryRunOptions>>,
+) -> Result<Json<Vec<DryRunResult>>, AppError> {
     info!("dry_run name={}", _name);
-    todo!()
+    let wp = _core
+        .processor_manager
+        .get_processor(&_name)
+        .ok_or_else(|| AppError::NotFound("Processor not found".into()))?;
+    let p = wp
+        .processor
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    let options = options.map(Into::into).unwrap_or_default();
+    Ok(Json(p.dry_run(options).await?))
 }
 
 #[axum::debug_handler]
@@ -142,19 +260,90 @@ async fn dry_run_stream(
     State(_code): State<Arc<CoreApplication>>,
     Path(_name): Path<String>,
     Json(_options): Json<Option<DryRunOptions>>,
-) -> () {
+) -> Result<Response, AppError> {
     // gen application/x-ndjson
     info!("dry_run_stream name={}", _name);
-    todo!()
+    let wp = _code
+        .processor_manager
+        .get_processor(&_name)
+        .ok_or_else(|| AppError::NotFound("Processor not found".into()))?;
+    let p = wp
+        .processor
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    let options = _options.map(Into::into).unwrap_or_default();
+    // 客户端断开后receiver被drop, processor会在下一个item前停止
+    let (sender, receiver) = tokio::sync::mpsc::channel(16);
+    let handle = tokio::spawn(async move { p.dry_run_stream(options, sender).await });
+    let state = DryRunStreamState {
+        receiver,
+        handle: Some(handle),
+        summary: DryRunSummary::default(),
+    };
+    let lines = stream::unfold(state, |mut state| async move {
+        if let Some(result) = state.receiver.recv().await {
+            state.summary.total += 1;
+            if result.status == ProcessingStatus::Filtered {
+                state.summary.filtered += 1;
+            }
+            return Some((to_ndjson_line(&result), state));
+        }
+        let handle = state.handle.take()?;
+        state.summary.error = match handle.await {
+            Ok(Ok(_)) => None,
+            Ok(Err(e)) => Some(e.message().to_string()),
+            Err(e) => Some(e.to_string()),
+        };
+        let line = to_ndjson_line(&json!({ "summary": &state.summary }));
+        Some((line, state))
+    });
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    )
+        .into_response())
+}
+
+struct DryRunStreamState {
+    receiver: tokio::sync::mpsc::Receiver<DryRunResult>,
+    handle: Option<JoinHandle<Result<(), ProcessingError>>>,
+    summary: DryRunSummary,
+}
+
+#[derive(Serialize, Default)]
+struct DryRunSummary {
+    total: usize,
+    filtered: usize,
+    error: Option<String>,
+}
+
+fn to_ndjson_line<T: Serialize>(value: &T) -> Result<String, serde_json::Error> {
+    serde_json::to_string(value).map(|mut line| {
+        line.push('\n');
+        line
+    })
 }
 
 #[axum::debug_handler]
 async fn trigger_rename(
     State(_core): State<Arc<CoreApplication>>,
     Path(_name): Path<String>,
-) -> () {
-    info!("trigger_rename name={}", _name);
-    todo!()
+) -> Result<(), AppError> {
+    let wp = _core
+        .processor_manager
+        .get_processor(&_name)
+        .ok_or_else(|| AppError::NotFound("Processor not found".into()))?;
+    let p = wp
+        .processor
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    if !p.is_async_downloader() {
+        return Err(AppError::BadRequest(
+            "Processor downloader is not async".into(),
+        ));
+    }
+    p.run_rename().await?;
+    Ok(())
 }
 
 #[axum::debug_handler]
@@ -162,19 +351,50 @@ async fn post_items(
     State(_core): State<Arc<CoreApplication>>,
     Path(_name): Path<String>,
     Json(items): Json<Vec<SourceItem>>,
-) -> () {
+) -> Result<(), AppError> {
     info!(
         "post_items name={}, items={}",
         _name,
         to_string(&items).unwrap()
     );
-    todo!()
+    let wp = _core
+        .processor_manager
+        .get_processor(&_name)
+        .ok_or_else(|| AppError::NotFound("Processor not found".into()))?;
+    let p = wp
+        .processor
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    p.run_items(items).await?;
+    Ok(())
 }
 
 #[axum::debug_handler]
-async fn get_state(State(_): State<Arc<CoreApplication>>, Path(name): Path<String>) -> () {
+async fn get_state(
+    State(ctx): State<Arc<ApplicationContext>>,
+    Path(name): Path<String>,
+) -> Result<Json<ProcessorState>, AppError> {
     info!("get_state name={}", name);
-    todo!()
+    let wp = ctx
+        .core
+        .processor_manager
+        .get_processor(&name)
+        .ok_or_else(|| AppError::NotFound("Processor not found".into()))?;
+    let p = wp
+        .processor
+        .clone()
+        .ok_or_else(|| AppError::BadRequest("Processor not running".into()))?;
+    let state = ctx
+        .storage
+        .find_processor_source_state(&p.name, &p.source_id)
+        .await
+        .map_err(|e| AppError::InternalError(e.message))?;
+    let health = p.source_health().await?;
+    Ok(Json(ProcessorState {
+        source_id: p.source_id.clone(),
+        last_pointer: state.map(|x| x.last_pointer),
+        health: health.into(),
+    }))
 }
 
 #[axum::debug_handler]
@@ -201,6 +421,11 @@ async fn delete_contents(
     todo!()
 }
 
+#[derive(Deserialize)]
+struct EventStreamParams {
+    name: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct PointerPayload {
     #[serde(rename = "sourceId")]
@@ -209,13 +434,26 @@ struct PointerPayload {
 }
 
 #[derive(Deserialize)]
-#[allow(dead_code)]
 struct QueryParams {
     name: Option<String>,
     size: Option<u32>,
+    /// 从0开始
     page: Option<u32>,
 }
 
+#[derive(Deserialize)]
+struct QueryRunsParams {
+    #[serde(rename = "maxId")]
+    max_id: Option<i64>,
+    limit: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CancelParams {
+    #[serde(rename = "cancelSubmitted")]
+    cancel_submitted: Option<bool>,
+}
+
 #[derive(Deserialize)]
 pub struct DryRunOptions {
     pub pointer: Option<Map<String, Value>>,
@@ -223,17 +461,45 @@ pub struct DryRunOptions {
     pub filter_processed: Option<bool>,
 }
 
+impl From<DryRunOptions> for source_processor::DryRunOptions {
+    fn from(value: DryRunOptions) -> Self {
+        Self {
+            pointer: value.pointer,
+            filter_processed: value.filter_processed.unwrap_or(true),
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct ProcessorInfo {
     pub name: String,
     pub enabled: bool,
     pub category: Option<String>,
     pub tags: HashSet<String>,
-    pub runtime: RuntimeSnapshot,
+    /// 处理器未创建成功或未启用时为空
+    pub runtime: Option<RuntimeSnapshot>,
     #[serde(rename = "errorMessage")]
     pub error_message: Option<String>,
 }
 
+impl ProcessorInfo {
+    fn new(core: &CoreApplication, config: ProcessorConfig) -> Self {
+        let wp = core.processor_manager.get_processor(&config.name);
+        let runtime = wp
+            .as_ref()
+            .and_then(|wp| wp.processor.as_ref())
+            .map(|p| p.runtime_snapshot().into());
+        Self {
+            name: config.name,
+            enabled: config.enabled,
+            category: config.category,
+            tags: config.tags,
+            runtime,
+            error_message: wp.and_then(|wp| wp.error_message.clone()),
+        }
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct RuntimeSnapshot {
@@ -243,3 +509,44 @@ struct RuntimeSnapshot {
     pub last_end_process_time: Option<UtcDateTime>,
     pub processing: bool,
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProcessorState {
+    pub source_id: String,
+    /// 未运行过时为空
+    pub last_pointer: Option<Value>,
+    pub health: SourceHealth,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SourceHealth {
+    pub consecutive_failures: u32,
+    pub last_active_at: Option<UtcDateTime>,
+    pub cooldown_until: Option<UtcDateTime>,
+    pub cooling_down: bool,
+}
+
+impl From<source_processor::SourceHealth> for SourceHealth {
+    fn from(value: source_processor::SourceHealth) -> Self {
+        Self {
+            cooling_down: value.is_cooling_down(OffsetDateTime::now_utc()),
+            consecutive_failures: value.consecutive_failures,
+            last_active_at: value.last_active_at.map(|x| x.to_utc()),
+            cooldown_until: value.cooldown_until.map(|x| x.to_utc()),
+        }
+    }
+}
+
+impl From<source_processor::RuntimeSnapshot> for RuntimeSnapshot {
+    fn from(value: source_processor::RuntimeSnapshot) -> Self {
+        Self {
+            created_at: value.created_at.to_utc(),
+            last_process_failed_message: value.last_process_failed_message,
+            last_start_process_time: value.last_start_process_time.map(|x| x.to_utc()),
+            last_end_process_time: value.last_end_process_time.map(|x| x.to_utc()),
+            processing: value.processing,
+        }
+    }
+}
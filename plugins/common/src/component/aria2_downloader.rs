@@ -0,0 +1,414 @@
+use crate::instance::aria2::Aria2Client;
+use parking_lot::Mutex;
+use source_downloader_sdk::async_trait::async_trait;
+use source_downloader_sdk::component::{
+    AsyncDownloader, ComponentError, ComponentSupplier, ComponentType, DownloadTask, Downloader,
+    ProcessingError, SdComponent, SdComponentMetadata, SourceFile,
+};
+use source_downloader_sdk::serde_json::{Map, Value, json};
+use source_downloader_sdk::{SdComponent, SourceItem};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+pub struct Aria2DownloaderSupplier {}
+
+pub const SUPPLIER: Aria2DownloaderSupplier = Aria2DownloaderSupplier {};
+
+impl ComponentSupplier for Aria2DownloaderSupplier {
+    fn supply_types(&self) -> Vec<ComponentType> {
+        vec![ComponentType::downloader("aria2".to_string())]
+    }
+
+    fn apply(&self, props: &Map<String, Value>) -> Result<Arc<dyn SdComponent>, ComponentError> {
+        let str_prop = |name: &str| props.get(name).and_then(Value::as_str).map(str::to_string);
+        let download_path = str_prop("download-path")
+            .ok_or_else(|| ComponentError::from("Missing 'download-path' property"))?;
+        let url = str_prop("url").unwrap_or_else(|| "http://localhost:6800/jsonrpc".to_string());
+        Ok(Arc::new(Aria2Downloader {
+            download_path,
+            client: Aria2Client::new(&url, str_prop("secret")),
+            gids: Mutex::new(HashMap::new()),
+        }))
+    }
+
+    fn get_metadata(&self) -> Option<Box<SdComponentMetadata>> {
+        None
+    }
+}
+
+#[derive(SdComponent)]
+#[component(Downloader, AsyncDownloader)]
+struct Aria2Downloader {
+    download_path: String,
+    client: Aria2Client,
+    // key为item hash
+    gids: Mutex<HashMap<String, Vec<String>>>,
+}
+
+impl Debug for Aria2Downloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Aria2Downloader")
+            .field("download_path", &self.download_path)
+            .finish()
+    }
+}
+
+impl Display for Aria2Downloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "aria2")
+    }
+}
+
+#[async_trait]
+impl Downloader for Aria2Downloader {
+    async fn submit(&self, task: &DownloadTask) -> Result<(), ProcessingError> {
+        let item_hash = task.source_item.hashing();
+        let dir = task.download_path.to_string_lossy().to_string();
+        let headers = task
+            .headers
+            .iter()
+            .flatten()
+            .map(|(k, v)| format!("{}: {}", k, v))
+            .collect::<Vec<_>>();
+        let mut options = Map::new();
+        options.insert("dir".to_string(), json!(dir));
+        if !headers.is_empty() {
+            options.insert("header".to_string(), json!(headers));
+        }
+
+        // 文件有单独的下载地址时每个文件一个任务, 否则整个item一个任务(如种子)
+        let mut downloads = vec![];
+        if task.download_files.iter().any(|f| f.download_uri.is_some()) {
+            for file in task.download_files {
+                let uri = file.download_uri.ok_or_else(|| {
+                    ProcessingError::non_retryable(format!(
+                        "File {} has no download uri",
+                        file.path.display()
+                    ))
+                })?;
+                let out = file
+                    .path
+                    .strip_prefix(task.download_path)
+                    .unwrap_or(file.path);
+                let mut options = options.clone();
+                options.insert("out".to_string(), json!(out.to_string_lossy()));
+                downloads.push((uri.to_string(), options));
+            }
+        } else {
+            downloads.push((task.source_item.download_uri.to_string(), options));
+        }
+
+        let mut gids = vec![];
+        for (index, (uri, mut options)) in downloads.into_iter().enumerate() {
+            let gid = gid(&item_hash, index);
+            // 重试时任务可能已经添加过了
+            if self.client.tell_status(&gid).await?.is_none() {
+                options.insert("gid".to_string(), json!(gid));
+                self.client.add_uri(&[uri], options).await?;
+            }
+            gids.push(gid);
+        }
+        info!("[aria2] submitted {} gids:{:?}", task.source_item, gids);
+        self.gids.lock().insert(item_hash, gids);
+        Ok(())
+    }
+
+    fn default_download_path(&self) -> &str {
+        &self.download_path
+    }
+
+    async fn cancel(&self, item: &SourceItem, _: &[SourceFile]) -> Result<(), ProcessingError> {
+        for gid in self.item_gids(item).await? {
+            if let Some(status) = self.client.tell_status(&gid).await? {
+                for followed in status.followed_by {
+                    self.client.remove(&followed).await?;
+                }
+            }
+            self.client.remove(&gid).await?;
+        }
+        self.gids.lock().remove(&item.hashing());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AsyncDownloader for Aria2Downloader {
+    async fn is_finished(&self, item: &SourceItem) -> Option<bool> {
+        let mut pending = match self.item_gids(item).await {
+            Ok(gids) if gids.is_empty() => return None,
+            Ok(gids) => gids,
+            // 请求失败时当作未完成, 避免被判定为任务丢失
+            Err(e) => {
+                debug!("[aria2] query {} failed: {}", item, e.message());
+                return Some(false);
+            }
+        };
+        let mut finished = true;
+        while let Some(gid) = pending.pop() {
+            let status = match self.client.tell_status(&gid).await {
+                Ok(status) => status?,
+                // 请求失败时当作未完成, 避免被判定为任务丢失
+                Err(e) => {
+                    debug!("[aria2] query {} failed: {}", gid, e.message());
+                    return Some(false);
+                }
+            };
+            match status.status.as_str() {
+                "complete" => pending.extend(status.followed_by),
+                "error" | "removed" => {
+                    debug!(
+                        "[aria2] {} is {} {:?}",
+                        gid, status.status, status.error_message
+                    );
+                    return None;
+                }
+                _ => finished = false,
+            }
+        }
+        Some(finished)
+    }
+}
+
+impl Aria2Downloader {
+    /// 重启后没有记录时按下标依次查询gid, 直到任务不存在
+    async fn item_gids(&self, item: &SourceItem) -> Result<Vec<String>, ProcessingError> {
+        let item_hash = item.hashing();
+        if let Some(gids) = self.gids.lock().get(&item_hash) {
+            return Ok(gids.clone());
+        }
+        let mut gids = vec![];
+        loop {
+            let gid = gid(&item_hash, gids.len());
+            if self.client.tell_status(&gid).await?.is_none() {
+                break;
+            }
+            gids.push(gid);
+        }
+        Ok(gids)
+    }
+}
+
+/// 由item hash生成固定的gid, 重启后仍然能找到任务
+fn gid(item_hash: &str, index: usize) -> String {
+    let base = item_hash
+        .get(..16)
+        .and_then(|x| u64::from_str_radix(x, 16).ok())
+        .unwrap_or_default();
+    format!("{:016x}", base.wrapping_add(index as u64))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::extract::State;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use source_downloader_sdk::component::SourceFileRef;
+    use source_downloader_sdk::time::OffsetDateTime;
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex as StdMutex;
+
+    #[derive(Default)]
+    struct MockState {
+        // gid -> (status, followedBy)
+        tasks: HashMap<String, (String, Vec<String>)>,
+        calls: Vec<(String, Vec<Value>)>,
+    }
+
+    type Shared = Arc<StdMutex<MockState>>;
+
+    async fn rpc(State(state): State<Shared>, Json(body): Json<Value>) -> Json<Value> {
+        let method = body["method"].as_str().unwrap().to_string();
+        let mut params = body["params"].as_array().unwrap().clone();
+        if params.first() != Some(&json!("token:secret")) {
+            return Json(
+                json!({"id": body["id"], "error": {"code": 1, "message": "Unauthorized"}}),
+            );
+        }
+        params.remove(0);
+        let mut state = state.lock().unwrap();
+        state.calls.push((method.clone(), params.clone()));
+        let not_found = |gid: &Value| json!({"id": body["id"], "error": {"code": 1, "message": format!("GID {} is not found", gid)}});
+        let result = match method.as_str() {
+            "aria2.addUri" => {
+                let gid = params[1]["gid"].as_str().unwrap().to_string();
+                state
+                    .tasks
+                    .insert(gid.clone(), ("active".to_string(), vec![]));
+                json!(gid)
+            }
+            "aria2.tellStatus" => match state.tasks.get(params[0].as_str().unwrap()) {
+                Some((status, followed_by)) => {
+                    json!({"status": status, "followedBy": followed_by})
+                }
+                None => return Json(not_found(&params[0])),
+            },
+            "aria2.forceRemove" | "aria2.removeDownloadResult" => {
+                match state.tasks.remove(params[0].as_str().unwrap()) {
+                    Some(_) => json!("OK"),
+                    None => return Json(not_found(&params[0])),
+                }
+            }
+            _ => {
+                return Json(
+                    json!({"id": body["id"], "error": {"code": 1, "message": "Method not found"}}),
+                );
+            }
+        };
+        Json(json!({"jsonrpc": "2.0", "id": body["id"], "result": result}))
+    }
+
+    async fn start_server() -> (String, Shared) {
+        let state = Shared::default();
+        let app = Router::new()
+            .route("/jsonrpc", post(rpc))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}/jsonrpc", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (addr, state)
+    }
+
+    fn downloader(url: &str) -> Arc<dyn SdComponent> {
+        let props = json!({"url": url, "secret": "secret", "download-path": "/downloads"});
+        SUPPLIER.apply(props.as_object().unwrap()).unwrap()
+    }
+
+    fn source_item() -> SourceItem {
+        SourceItem {
+            title: "test".to_string(),
+            link: "http://localhost/test".parse().unwrap(),
+            datetime: OffsetDateTime::now_utc(),
+            content_type: "application/x-bittorrent".to_string(),
+            download_uri: "http://localhost/test.torrent".parse().unwrap(),
+            attrs: Default::default(),
+            tags: Default::default(),
+            identity: None,
+        }
+    }
+
+    fn set_status(state: &Shared, gid: &str, status: &str, followed_by: Vec<String>) {
+        state
+            .lock()
+            .unwrap()
+            .tasks
+            .insert(gid.to_string(), (status.to_string(), followed_by));
+    }
+
+    #[tokio::test]
+    async fn submit_item_and_follow_torrent() {
+        let (url, state) = start_server().await;
+        let component = downloader(&url);
+        let downloader = component.clone().as_async_downloader().unwrap();
+        let item = source_item();
+        let key = "Cookie".to_string();
+        let value = "a=b".to_string();
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &[],
+            download_path: Path::new("/downloads/show"),
+            category: &None,
+            tags: None,
+            headers: Some(HashMap::from([(&key, &value)])),
+        };
+        downloader.submit(&task).await.unwrap();
+
+        let (_, params) = state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .find(|(m, _)| m == "aria2.addUri")
+            .cloned()
+            .unwrap();
+        let gid = gid(&item.hashing(), 0);
+        assert_eq!(params[0], json!(["http://localhost/test.torrent"]));
+        assert_eq!(params[1]["dir"], "/downloads/show");
+        assert_eq!(params[1]["header"], json!(["Cookie: a=b"]));
+        assert_eq!(params[1]["gid"], gid);
+
+        assert_eq!(downloader.is_finished(&item).await, Some(false));
+        set_status(
+            &state,
+            &gid,
+            "complete",
+            vec!["00000000000000bt".to_string()],
+        );
+        set_status(&state, "00000000000000bt", "active", vec![]);
+        assert_eq!(downloader.is_finished(&item).await, Some(false));
+        set_status(&state, "00000000000000bt", "complete", vec![]);
+        assert_eq!(downloader.is_finished(&item).await, Some(true));
+
+        downloader.cancel(&item, &[]).await.unwrap();
+        assert!(state.lock().unwrap().tasks.is_empty());
+        assert_eq!(downloader.is_finished(&item).await, None);
+    }
+
+    #[tokio::test]
+    async fn submit_files() {
+        let (url, state) = start_server().await;
+        // 模拟重启后的实例
+        let restarted = downloader(&url).as_async_downloader().unwrap();
+        let downloader = downloader(&url).as_async_downloader().unwrap();
+        let item = source_item();
+        let attrs = Map::new();
+        let paths = [
+            PathBuf::from("/downloads/a/1.txt"),
+            PathBuf::from("/downloads/a/2.txt"),
+        ];
+        let uris = [
+            "http://localhost/1.txt".parse().unwrap(),
+            "http://localhost/2.txt".parse().unwrap(),
+        ];
+        let files = paths
+            .iter()
+            .zip(uris.iter())
+            .map(|(path, uri)| SourceFileRef {
+                path,
+                attrs: &attrs,
+                download_uri: Some(uri),
+                tags: &[],
+                data: None,
+            })
+            .collect::<Vec<_>>();
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &files,
+            download_path: Path::new("/downloads"),
+            category: &None,
+            tags: None,
+            headers: None,
+        };
+        downloader.submit(&task).await.unwrap();
+        // 重试时不重复添加
+        downloader.submit(&task).await.unwrap();
+
+        let adds = state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|(m, _)| m == "aria2.addUri")
+            .map(|(_, p)| p.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(adds.len(), 2);
+        assert_eq!(adds[0][1]["out"], "a/1.txt");
+        assert_eq!(adds[1][1]["out"], "a/2.txt");
+        assert_ne!(adds[0][1]["gid"], adds[1][1]["gid"]);
+
+        let gids = [gid(&item.hashing(), 0), gid(&item.hashing(), 1)];
+        set_status(&state, &gids[0], "complete", vec![]);
+        assert_eq!(downloader.is_finished(&item).await, Some(false));
+
+        // 没有记录时依次查询所有文件的gid
+        assert_eq!(restarted.is_finished(&item).await, Some(false));
+        set_status(&state, &gids[1], "error", vec![]);
+        assert_eq!(downloader.is_finished(&item).await, None);
+        assert_eq!(restarted.is_finished(&item).await, None);
+        restarted.cancel(&item, &[]).await.unwrap();
+        assert!(state.lock().unwrap().tasks.is_empty());
+        assert_eq!(restarted.is_finished(&item).await, None);
+    }
+}
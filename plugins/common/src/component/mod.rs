@@ -1 +1,4 @@
-pub mod mikan_source;
\ No newline at end of file
+pub mod aria2_downloader;
+pub mod mikan_source;
+pub mod qbittorrent_downloader;
+pub mod transmission_downloader;
@@ -0,0 +1,415 @@
+use crate::instance::qbittorrent::{AddTorrent, QbittorrentClient, TorrentFile};
+use crate::util;
+use source_downloader_sdk::async_trait::async_trait;
+use source_downloader_sdk::component::{
+    AsyncDownloader, ComponentError, ComponentSupplier, ComponentType, DownloadTask, Downloader,
+    ProcessingError, SdComponent, SdComponentMetadata, SourceFile,
+};
+use source_downloader_sdk::serde_json::{Map, Value};
+use source_downloader_sdk::{SdComponent, SourceItem};
+use std::collections::HashSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+const METADATA_ATTEMPTS: u32 = 10;
+const METADATA_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct QbittorrentDownloaderSupplier {}
+
+pub const SUPPLIER: QbittorrentDownloaderSupplier = QbittorrentDownloaderSupplier {};
+
+impl ComponentSupplier for QbittorrentDownloaderSupplier {
+    fn supply_types(&self) -> Vec<ComponentType> {
+        vec![ComponentType::downloader("qbittorrent".to_string())]
+    }
+
+    fn apply(&self, props: &Map<String, Value>) -> Result<Arc<dyn SdComponent>, ComponentError> {
+        let str_prop = |name: &str| props.get(name).and_then(Value::as_str).map(str::to_string);
+        let download_path = str_prop("download-path")
+            .ok_or_else(|| ComponentError::from("Missing 'download-path' property"))?;
+        let url = str_prop("url").unwrap_or_else(|| "http://localhost:8080".to_string());
+        Ok(Arc::new(QbittorrentDownloader {
+            download_path,
+            client: QbittorrentClient::new(&url, str_prop("username"), str_prop("password")),
+        }))
+    }
+
+    fn get_metadata(&self) -> Option<Box<SdComponentMetadata>> {
+        None
+    }
+}
+
+#[derive(SdComponent)]
+#[component(Downloader, AsyncDownloader)]
+struct QbittorrentDownloader {
+    download_path: String,
+    client: QbittorrentClient,
+}
+
+impl Debug for QbittorrentDownloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QbittorrentDownloader")
+            .field("download_path", &self.download_path)
+            .finish()
+    }
+}
+
+impl Display for QbittorrentDownloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "qbittorrent")
+    }
+}
+
+#[async_trait]
+impl Downloader for QbittorrentDownloader {
+    async fn submit(&self, task: &DownloadTask) -> Result<(), ProcessingError> {
+        let hash = torrent_hash(task.source_item)?;
+        // 重试时种子可能已经添加过了
+        if self.client.torrent_info(&hash).await?.is_none() {
+            self.client
+                .add_torrent(&AddTorrent {
+                    urls: task.source_item.download_uri.to_string(),
+                    save_path: task.download_path.to_string_lossy().to_string(),
+                    category: task.category.clone(),
+                    tags: task.tags.map(|x| x.to_vec()).unwrap_or_default(),
+                    paused: true,
+                })
+                .await?;
+        }
+
+        let wanted = task
+            .download_files
+            .iter()
+            .map(|f| f.path.strip_prefix(task.download_path).unwrap_or(f.path))
+            .collect::<HashSet<_>>();
+        if !wanted.is_empty() {
+            let files = self.wait_for_files(&hash).await?;
+            let unwanted = files
+                .iter()
+                .filter(|f| !wanted.contains(Path::new(&f.name)))
+                .map(|f| f.index)
+                .collect::<Vec<_>>();
+            // 路径全部不匹配时说明解析的文件和种子不一致, 不能取消选择所有文件
+            if unwanted.len() == files.len() {
+                return Err(ProcessingError::non_retryable(format!(
+                    "None of the download files match the files of torrent {}",
+                    hash
+                )));
+            }
+            if !unwanted.is_empty() {
+                debug!(
+                    "[qbittorrent] {} skip {}/{} files",
+                    hash,
+                    unwanted.len(),
+                    files.len()
+                );
+                self.client.set_file_priority(&hash, &unwanted, 0).await?;
+            }
+        }
+        self.client.resume(&hash).await?;
+        info!("[qbittorrent] submitted {} {}", hash, task.source_item);
+        Ok(())
+    }
+
+    fn default_download_path(&self) -> &str {
+        &self.download_path
+    }
+
+    async fn cancel(&self, item: &SourceItem, _: &[SourceFile]) -> Result<(), ProcessingError> {
+        let hash = torrent_hash(item)?;
+        self.client.delete(&hash, true).await
+    }
+}
+
+#[async_trait]
+impl AsyncDownloader for QbittorrentDownloader {
+    async fn is_finished(&self, item: &SourceItem) -> Option<bool> {
+        let hash = util::torrent_hash(item)?;
+        match self.client.torrent_info(&hash).await {
+            Ok(info) => info.map(|x| x.progress >= 1.0),
+            // 请求失败时当作未完成, 避免被判定为任务丢失
+            Err(e) => {
+                debug!("[qbittorrent] query {} failed: {}", hash, e.message());
+                Some(false)
+            }
+        }
+    }
+}
+
+impl QbittorrentDownloader {
+    /// 磁力链接需要等待元数据下载完成才有文件列表
+    async fn wait_for_files(&self, hash: &str) -> Result<Vec<TorrentFile>, ProcessingError> {
+        for _ in 0..METADATA_ATTEMPTS {
+            let files = self.client.torrent_files(hash).await?;
+            if !files.is_empty() {
+                return Ok(files);
+            }
+            tokio::time::sleep(METADATA_INTERVAL).await;
+        }
+        Err(ProcessingError::retryable(format!(
+            "qBittorrent torrent {} metadata is not ready",
+            hash
+        )))
+    }
+}
+
+fn torrent_hash(item: &SourceItem) -> Result<String, ProcessingError> {
+    util::torrent_hash(item).ok_or_else(|| {
+        ProcessingError::non_retryable(format!("Cannot resolve torrent hash of {}", item))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::post;
+    use axum::{Form, Json, Router};
+    use source_downloader_sdk::component::SourceFileRef;
+    use source_downloader_sdk::serde_json::json;
+    use source_downloader_sdk::time::OffsetDateTime;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    type Params = HashMap<String, String>;
+
+    #[derive(Default)]
+    struct MockState {
+        torrent: Option<Params>,
+        progress: f64,
+        calls: Vec<(String, Params)>,
+    }
+
+    type Shared = Arc<Mutex<MockState>>;
+
+    fn authorized(headers: &HeaderMap) -> bool {
+        headers
+            .get("cookie")
+            .is_some_and(|x| x.to_str().unwrap() == "SID=mock-sid")
+    }
+
+    async fn login(Form(params): Form<Params>) -> impl IntoResponse {
+        if params["username"] == "admin" && params["password"] == "adminadmin" {
+            ([("set-cookie", "SID=mock-sid; HttpOnly; path=/")], "Ok.").into_response()
+        } else {
+            "Fails.".into_response()
+        }
+    }
+
+    async fn api(
+        State(state): State<Shared>,
+        axum::extract::Path(action): axum::extract::Path<String>,
+        headers: HeaderMap,
+        Form(params): Form<Params>,
+    ) -> axum::response::Response {
+        if !authorized(&headers) {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+        let mut state = state.lock().unwrap();
+        state.calls.push((action.clone(), params.clone()));
+        match action.as_str() {
+            "add" => {
+                state.torrent = Some(params);
+                "Ok.".into_response()
+            }
+            "info" => match &state.torrent {
+                Some(_) if params["hashes"] == HASH => Json(json!([
+                    {"hash": HASH, "progress": state.progress, "state": "downloading"}
+                ]))
+                .into_response(),
+                _ => Json(json!([])).into_response(),
+            },
+            "files" => Json(json!([
+                {"index": 0, "name": "show/01.mkv", "priority": 1},
+                {"index": 1, "name": "show/sample.mkv", "priority": 1},
+                {"index": 2, "name": "show/02.mkv", "priority": 1}
+            ]))
+            .into_response(),
+            "delete" => {
+                state.torrent = None;
+                StatusCode::OK.into_response()
+            }
+            "filePrio" | "resume" => StatusCode::OK.into_response(),
+            _ => StatusCode::NOT_FOUND.into_response(),
+        }
+    }
+
+    async fn start_server() -> (String, Shared) {
+        let state = Shared::default();
+        let app = Router::new()
+            .route("/api/v2/auth/login", post(login))
+            .route("/api/v2/torrents/{action}", post(api))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (addr, state)
+    }
+
+    fn downloader(url: &str) -> Arc<dyn SdComponent> {
+        let props = json!({
+            "url": url,
+            "username": "admin",
+            "password": "adminadmin",
+            "download-path": "/downloads",
+        });
+        SUPPLIER.apply(props.as_object().unwrap()).unwrap()
+    }
+
+    fn source_item(url: &str) -> SourceItem {
+        let uri = format!("{}/Download/20250101/{}.torrent", url, HASH);
+        SourceItem {
+            title: "test".to_string(),
+            link: uri.parse().unwrap(),
+            datetime: OffsetDateTime::now_utc(),
+            content_type: "application/x-bittorrent".to_string(),
+            download_uri: uri.parse().unwrap(),
+            attrs: Default::default(),
+            tags: Default::default(),
+            identity: None,
+        }
+    }
+
+    fn calls(state: &Shared, action: &str) -> Vec<Params> {
+        state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|(a, _)| a == action)
+            .map(|(_, p)| p.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn submit_selected_files() {
+        let (url, state) = start_server().await;
+        let downloader = downloader(&url).as_downloader().unwrap();
+        let item = source_item(&url);
+        let attrs = Map::new();
+        let paths = [
+            PathBuf::from("/downloads/show/01.mkv"),
+            PathBuf::from("/downloads/show/02.mkv"),
+        ];
+        let files = paths
+            .iter()
+            .map(|path| SourceFileRef {
+                path,
+                attrs: &attrs,
+                download_uri: None,
+                tags: &[],
+                data: None,
+            })
+            .collect::<Vec<_>>();
+        let tags = vec!["anime".to_string(), "sd".to_string()];
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &files,
+            download_path: Path::new("/downloads"),
+            category: &Some("tv".to_string()),
+            tags: Some(&tags),
+            headers: None,
+        };
+        downloader.submit(&task).await.unwrap();
+
+        let add = &calls(&state, "add")[0];
+        assert_eq!(add["urls"], item.download_uri.to_string());
+        assert_eq!(add["savepath"], "/downloads");
+        assert_eq!(add["category"], "tv");
+        assert_eq!(add["tags"], "anime,sd");
+        assert_eq!(add["paused"], "true");
+        let priority = &calls(&state, "filePrio")[0];
+        assert_eq!(priority["hash"], HASH);
+        assert_eq!(priority["id"], "1");
+        assert_eq!(priority["priority"], "0");
+        assert_eq!(calls(&state, "resume")[0]["hashes"], HASH);
+
+        // 重试时不重复添加
+        downloader.submit(&task).await.unwrap();
+        assert_eq!(calls(&state, "add").len(), 1);
+    }
+
+    #[tokio::test]
+    async fn submit_unmatched_files() {
+        let (url, state) = start_server().await;
+        let downloader = downloader(&url).as_downloader().unwrap();
+        let item = source_item(&url);
+        let attrs = Map::new();
+        let path = PathBuf::from("/downloads/other/01.mkv");
+        let files = [SourceFileRef {
+            path: &path,
+            attrs: &attrs,
+            download_uri: None,
+            tags: &[],
+            data: None,
+        }];
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &files,
+            download_path: Path::new("/downloads"),
+            category: &None,
+            tags: None,
+            headers: None,
+        };
+        let err = downloader.submit(&task).await.unwrap_err();
+        assert!(matches!(err, ProcessingError::NonRetryable { .. }));
+        assert!(calls(&state, "filePrio").is_empty());
+        assert!(calls(&state, "resume").is_empty());
+    }
+
+    #[tokio::test]
+    async fn finished_and_cancel() {
+        let (url, state) = start_server().await;
+        let component = downloader(&url);
+        let downloader = component.clone().as_async_downloader().unwrap();
+        let item = source_item(&url);
+        assert_eq!(downloader.is_finished(&item).await, None);
+
+        state.lock().unwrap().torrent = Some(Params::new());
+        state.lock().unwrap().progress = 0.5;
+        assert_eq!(downloader.is_finished(&item).await, Some(false));
+        state.lock().unwrap().progress = 1.0;
+        assert_eq!(downloader.is_finished(&item).await, Some(true));
+
+        downloader.cancel(&item, &[]).await.unwrap();
+        let delete = &calls(&state, "delete")[0];
+        assert_eq!(delete["hashes"], HASH);
+        assert_eq!(delete["deleteFiles"], "true");
+        assert_eq!(downloader.is_finished(&item).await, None);
+    }
+
+    #[tokio::test]
+    async fn login_failed() {
+        let (url, _) = start_server().await;
+        let props = json!({"url": url, "username": "admin", "password": "wrong", "download-path": "/downloads"});
+        let downloader = SUPPLIER
+            .apply(props.as_object().unwrap())
+            .unwrap()
+            .as_downloader()
+            .unwrap();
+        let err = downloader
+            .cancel(&source_item(&url), &[])
+            .await
+            .unwrap_err();
+        assert!(err.message().contains("login failed"));
+    }
+
+    #[test]
+    fn resolve_torrent_hash() {
+        let mut item = source_item("http://localhost");
+        assert_eq!(util::torrent_hash(&item).unwrap(), HASH);
+        item.download_uri = "http://localhost/a.torrent".parse().unwrap();
+        assert!(util::torrent_hash(&item).is_none());
+        item.attrs
+            .insert("torrent-hash".to_string(), Value::from(HASH.to_uppercase()));
+        assert_eq!(util::torrent_hash(&item).unwrap(), HASH);
+    }
+}
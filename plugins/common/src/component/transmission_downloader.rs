@@ -0,0 +1,399 @@
+use crate::instance::transmission::{TorrentFile, TransmissionClient};
+use crate::util;
+use source_downloader_sdk::async_trait::async_trait;
+use source_downloader_sdk::component::{
+    AsyncDownloader, ComponentError, ComponentSupplier, ComponentType, DownloadTask, Downloader,
+    ProcessingError, SdComponent, SdComponentMetadata, SourceFile,
+};
+use source_downloader_sdk::serde_json::{Map, Value, json};
+use source_downloader_sdk::{SdComponent, SourceItem};
+use std::collections::HashSet;
+use std::fmt::{Debug, Display, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+const METADATA_ATTEMPTS: u32 = 10;
+const METADATA_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct TransmissionDownloaderSupplier {}
+
+pub const SUPPLIER: TransmissionDownloaderSupplier = TransmissionDownloaderSupplier {};
+
+impl ComponentSupplier for TransmissionDownloaderSupplier {
+    fn supply_types(&self) -> Vec<ComponentType> {
+        vec![ComponentType::downloader("transmission".to_string())]
+    }
+
+    fn apply(&self, props: &Map<String, Value>) -> Result<Arc<dyn SdComponent>, ComponentError> {
+        let str_prop = |name: &str| props.get(name).and_then(Value::as_str).map(str::to_string);
+        let download_path = str_prop("download-path")
+            .ok_or_else(|| ComponentError::from("Missing 'download-path' property"))?;
+        let url =
+            str_prop("url").unwrap_or_else(|| "http://localhost:9091/transmission/rpc".to_string());
+        Ok(Arc::new(TransmissionDownloader {
+            download_path,
+            client: TransmissionClient::new(&url, str_prop("username"), str_prop("password")),
+        }))
+    }
+
+    fn get_metadata(&self) -> Option<Box<SdComponentMetadata>> {
+        None
+    }
+}
+
+#[derive(SdComponent)]
+#[component(Downloader, AsyncDownloader)]
+struct TransmissionDownloader {
+    download_path: String,
+    client: TransmissionClient,
+}
+
+impl Debug for TransmissionDownloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TransmissionDownloader")
+            .field("download_path", &self.download_path)
+            .finish()
+    }
+}
+
+impl Display for TransmissionDownloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "transmission")
+    }
+}
+
+#[async_trait]
+impl Downloader for TransmissionDownloader {
+    async fn submit(&self, task: &DownloadTask) -> Result<(), ProcessingError> {
+        // 重启后需要从item中解析出hash查询状态, 解析不出时不提交
+        let hash = torrent_hash(task.source_item)?;
+        // Transmission没有分类, 和tags一起作为labels
+        let labels = task
+            .category
+            .iter()
+            .chain(task.tags.into_iter().flatten())
+            .collect::<Vec<_>>();
+        let mut arguments = json!({
+            "filename": task.source_item.download_uri.to_string(),
+            "download-dir": task.download_path.to_string_lossy(),
+            "paused": true,
+        });
+        if !labels.is_empty() {
+            arguments["labels"] = json!(labels);
+        }
+        // 重试时返回已存在的种子
+        let added = self.client.add_torrent(arguments).await?;
+        if !added.eq_ignore_ascii_case(&hash) {
+            return Err(ProcessingError::non_retryable(format!(
+                "Transmission added torrent {} but the item hash is {}",
+                added, hash
+            )));
+        }
+
+        let wanted = task
+            .download_files
+            .iter()
+            .map(|f| f.path.strip_prefix(task.download_path).unwrap_or(f.path))
+            .collect::<HashSet<_>>();
+        if !wanted.is_empty() {
+            let files = self.wait_for_files(&hash).await?;
+            let (files_wanted, files_unwanted): (Vec<_>, Vec<_>) =
+                (0..files.len()).partition(|i| wanted.contains(Path::new(&files[*i].name)));
+            // 路径全部不匹配时说明解析的文件和种子不一致, 空的files-wanted会选择所有文件
+            if files_wanted.is_empty() {
+                return Err(ProcessingError::non_retryable(format!(
+                    "None of the download files match the files of torrent {}",
+                    hash
+                )));
+            }
+            if !files_unwanted.is_empty() {
+                debug!(
+                    "[transmission] {} skip {}/{} files",
+                    hash,
+                    files_unwanted.len(),
+                    files.len()
+                );
+                self.client
+                    .set_files_wanted(&hash, &files_wanted, &files_unwanted)
+                    .await?;
+            }
+        }
+        self.client.start(&hash).await?;
+        info!("[transmission] submitted {} {}", hash, task.source_item);
+        Ok(())
+    }
+
+    fn default_download_path(&self) -> &str {
+        &self.download_path
+    }
+
+    async fn cancel(&self, item: &SourceItem, _: &[SourceFile]) -> Result<(), ProcessingError> {
+        let hash = torrent_hash(item)?;
+        self.client.remove(&hash, true).await
+    }
+}
+
+#[async_trait]
+impl AsyncDownloader for TransmissionDownloader {
+    async fn is_finished(&self, item: &SourceItem) -> Option<bool> {
+        let hash = util::torrent_hash(item)?;
+        match self.client.torrent_get(&hash, &["percentDone"]).await {
+            Ok(torrent) => torrent.map(|x| x.percent_done >= 1.0),
+            // 请求失败时当作未完成, 避免被判定为任务丢失
+            Err(e) => {
+                debug!("[transmission] query {} failed: {}", hash, e.message());
+                Some(false)
+            }
+        }
+    }
+}
+
+impl TransmissionDownloader {
+    /// 磁力链接需要等待元数据下载完成才有文件列表
+    async fn wait_for_files(&self, hash: &str) -> Result<Vec<TorrentFile>, ProcessingError> {
+        for _ in 0..METADATA_ATTEMPTS {
+            if let Some(torrent) = self.client.torrent_get(hash, &["files"]).await?
+                && !torrent.files.is_empty()
+            {
+                return Ok(torrent.files);
+            }
+            tokio::time::sleep(METADATA_INTERVAL).await;
+        }
+        Err(ProcessingError::retryable(format!(
+            "Transmission torrent {} metadata is not ready",
+            hash
+        )))
+    }
+}
+
+fn torrent_hash(item: &SourceItem) -> Result<String, ProcessingError> {
+    util::torrent_hash(item).ok_or_else(|| {
+        ProcessingError::non_retryable(format!("Cannot resolve torrent hash of {}", item))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::extract::State;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use source_downloader_sdk::component::SourceFileRef;
+    use source_downloader_sdk::time::OffsetDateTime;
+    use std::path::PathBuf;
+    use std::sync::Mutex as StdMutex;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+    const SESSION_ID: &str = "session-1";
+
+    #[derive(Default)]
+    struct MockState {
+        added: bool,
+        percent_done: f64,
+        calls: Vec<Value>,
+        handshakes: u32,
+    }
+
+    type Shared = Arc<StdMutex<MockState>>;
+
+    async fn rpc(
+        State(state): State<Shared>,
+        headers: HeaderMap,
+        Json(body): Json<Value>,
+    ) -> Response {
+        let mut state = state.lock().unwrap();
+        if headers
+            .get("X-Transmission-Session-Id")
+            .is_none_or(|x| x != SESSION_ID)
+        {
+            state.handshakes += 1;
+            return (
+                StatusCode::CONFLICT,
+                [("X-Transmission-Session-Id", SESSION_ID)],
+            )
+                .into_response();
+        }
+        state.calls.push(body.clone());
+        let arguments = match body["method"].as_str().unwrap() {
+            "torrent-add" => {
+                let key = if state.added {
+                    "torrent-duplicate"
+                } else {
+                    "torrent-added"
+                };
+                state.added = true;
+                json!({key: {"id": 1, "hashString": HASH, "name": "show"}})
+            }
+            "torrent-get" if state.added => json!({"torrents": [{
+                "hashString": HASH,
+                "percentDone": state.percent_done,
+                "files": [
+                    {"name": "show/01.mkv"},
+                    {"name": "show/sample.mkv"},
+                    {"name": "show/02.mkv"}
+                ]
+            }]}),
+            "torrent-get" => json!({"torrents": []}),
+            "torrent-remove" => {
+                state.added = false;
+                json!({})
+            }
+            "torrent-set" | "torrent-start" => json!({}),
+            _ => return Json(json!({"result": "method name not recognized"})).into_response(),
+        };
+        Json(json!({"result": "success", "arguments": arguments})).into_response()
+    }
+
+    async fn start_server() -> (String, Shared) {
+        let state = Shared::default();
+        let app = Router::new()
+            .route("/transmission/rpc", post(rpc))
+            .with_state(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}/transmission/rpc", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (addr, state)
+    }
+
+    fn downloader(url: &str) -> Arc<dyn SdComponent> {
+        let props = json!({"url": url, "download-path": "/downloads"});
+        SUPPLIER.apply(props.as_object().unwrap()).unwrap()
+    }
+
+    fn source_item() -> SourceItem {
+        let uri = format!("http://localhost/{}.torrent", HASH);
+        SourceItem {
+            title: "test".to_string(),
+            link: uri.parse().unwrap(),
+            datetime: OffsetDateTime::now_utc(),
+            content_type: "application/x-bittorrent".to_string(),
+            download_uri: uri.parse().unwrap(),
+            attrs: Default::default(),
+            tags: Default::default(),
+            identity: None,
+        }
+    }
+
+    fn calls(state: &Shared, method: &str) -> Vec<Value> {
+        state
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .filter(|x| x["method"] == method)
+            .map(|x| x["arguments"].clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn submit_and_finish() {
+        let (url, state) = start_server().await;
+        let downloader = downloader(&url).as_async_downloader().unwrap();
+        let item = source_item();
+        let attrs = Map::new();
+        let paths = [
+            PathBuf::from("/downloads/show/01.mkv"),
+            PathBuf::from("/downloads/show/02.mkv"),
+        ];
+        let files = paths
+            .iter()
+            .map(|path| SourceFileRef {
+                path,
+                attrs: &attrs,
+                download_uri: None,
+                tags: &[],
+                data: None,
+            })
+            .collect::<Vec<_>>();
+        let tags = vec!["anime".to_string()];
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &files,
+            download_path: Path::new("/downloads"),
+            category: &Some("tv".to_string()),
+            tags: Some(&tags),
+            headers: None,
+        };
+        downloader.submit(&task).await.unwrap();
+        assert_eq!(state.lock().unwrap().handshakes, 1);
+
+        let add = &calls(&state, "torrent-add")[0];
+        assert_eq!(add["filename"], item.download_uri.to_string());
+        assert_eq!(add["download-dir"], "/downloads");
+        assert_eq!(add["paused"], true);
+        assert_eq!(add["labels"], json!(["tv", "anime"]));
+        let set = &calls(&state, "torrent-set")[0];
+        assert_eq!(set["ids"], json!([HASH]));
+        assert_eq!(set["files-wanted"], json!([0, 2]));
+        assert_eq!(set["files-unwanted"], json!([1]));
+        assert_eq!(calls(&state, "torrent-start")[0]["ids"], json!([HASH]));
+
+        // 重试时使用已存在的种子
+        downloader.submit(&task).await.unwrap();
+        assert_eq!(calls(&state, "torrent-add").len(), 2);
+
+        assert_eq!(downloader.is_finished(&item).await, Some(false));
+        state.lock().unwrap().percent_done = 1.0;
+        assert_eq!(downloader.is_finished(&item).await, Some(true));
+
+        downloader.cancel(&item, &[]).await.unwrap();
+        let remove = &calls(&state, "torrent-remove")[0];
+        assert_eq!(remove["ids"], json!([HASH]));
+        assert_eq!(remove["delete-local-data"], true);
+        assert_eq!(downloader.is_finished(&item).await, None);
+    }
+
+    #[tokio::test]
+    async fn submit_unmatched_files() {
+        let (url, state) = start_server().await;
+        let downloader = downloader(&url).as_downloader().unwrap();
+        let item = source_item();
+        let attrs = Map::new();
+        let path = PathBuf::from("/downloads/other/01.mkv");
+        let files = [SourceFileRef {
+            path: &path,
+            attrs: &attrs,
+            download_uri: None,
+            tags: &[],
+            data: None,
+        }];
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &files,
+            download_path: Path::new("/downloads"),
+            category: &None,
+            tags: None,
+            headers: None,
+        };
+        let err = downloader.submit(&task).await.unwrap_err();
+        assert!(matches!(err, ProcessingError::NonRetryable { .. }));
+        assert!(calls(&state, "torrent-set").is_empty());
+        assert!(calls(&state, "torrent-start").is_empty());
+    }
+
+    #[tokio::test]
+    async fn unknown_torrent() {
+        let (url, state) = start_server().await;
+        let downloader = downloader(&url).as_async_downloader().unwrap();
+        assert_eq!(downloader.is_finished(&source_item()).await, None);
+
+        // 解析不出hash时不提交
+        let mut item = source_item();
+        item.download_uri = "http://localhost/show.torrent".parse().unwrap();
+        let task = DownloadTask {
+            source_item: &item,
+            download_files: &[],
+            download_path: Path::new("/downloads"),
+            category: &None,
+            tags: None,
+            headers: None,
+        };
+        assert!(downloader.submit(&task).await.is_err());
+        assert!(calls(&state, "torrent-add").is_empty());
+        assert!(downloader.cancel(&item, &[]).await.is_err());
+    }
+}
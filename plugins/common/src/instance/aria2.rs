@@ -0,0 +1,121 @@
+use reqwest::Client;
+use serde::Deserialize;
+use source_downloader_sdk::component::ProcessingError;
+use source_downloader_sdk::serde_json::{Map, Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// aria2 JSON-RPC 客户端
+pub struct Aria2Client {
+    url: String,
+    secret: Option<String>,
+    http_client: Client,
+    id: AtomicU64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Aria2Status {
+    /// active, waiting, paused, error, complete, removed
+    pub status: String,
+    /// 种子文件下载完成后自动开始的BT任务
+    #[serde(default)]
+    pub followed_by: Vec<String>,
+    pub error_message: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl Aria2Client {
+    pub fn new(url: &str, secret: Option<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            url: url.to_string(),
+            secret,
+            http_client: client,
+            id: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn add_uri(
+        &self,
+        uris: &[String],
+        options: Map<String, Value>,
+    ) -> Result<String, ProcessingError> {
+        let result = self
+            .call("aria2.addUri", vec![json!(uris), Value::Object(options)])
+            .await?;
+        result
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| ProcessingError::retryable(format!("Invalid aria2 gid {}", result)))
+    }
+
+    /// gid不存在时返回None
+    pub async fn tell_status(&self, gid: &str) -> Result<Option<Aria2Status>, ProcessingError> {
+        let keys = json!(["status", "followedBy", "errorMessage"]);
+        match self.call("aria2.tellStatus", vec![json!(gid), keys]).await {
+            Ok(result) => source_downloader_sdk::serde_json::from_value(result)
+                .map(Some)
+                .map_err(|e| ProcessingError::retryable(format!("Invalid aria2 status: {}", e))),
+            Err(e) if e.message().contains("is not found") => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 删除任务及其下载结果, 任务不存在时忽略
+    pub async fn remove(&self, gid: &str) -> Result<(), ProcessingError> {
+        for method in ["aria2.forceRemove", "aria2.removeDownloadResult"] {
+            match self.call(method, vec![json!(gid)]).await {
+                Err(e) if !e.message().contains("is not found") => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    async fn call(&self, method: &str, params: Vec<Value>) -> Result<Value, ProcessingError> {
+        let mut params = params;
+        if let Some(secret) = &self.secret {
+            params.insert(0, json!(format!("token:{}", secret)));
+        }
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": self.id.fetch_add(1, Ordering::Relaxed).to_string(),
+            "method": method,
+            "params": params,
+        });
+        let response: RpcResponse = self
+            .http_client
+            .post(&self.url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProcessingError::retryable(format!("aria2 {} failed: {}", method, e)))?
+            .json()
+            .await
+            .map_err(|e| {
+                ProcessingError::retryable(format!("Invalid aria2 {} response: {}", method, e))
+            })?;
+        if let Some(error) = response.error {
+            return Err(ProcessingError::non_retryable(format!(
+                "aria2 {} error {}: {}",
+                method, error.code, error.message
+            )));
+        }
+        Ok(response.result.unwrap_or(Value::Null))
+    }
+}
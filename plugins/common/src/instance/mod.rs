@@ -1 +1,4 @@
-pub mod mikan;
\ No newline at end of file
+pub mod aria2;
+pub mod mikan;
+pub mod qbittorrent;
+pub mod transmission;
@@ -0,0 +1,222 @@
+use parking_lot::RwLock;
+use reqwest::{Client, Response, StatusCode};
+use serde::Deserialize;
+use source_downloader_sdk::component::ProcessingError;
+use source_downloader_sdk::http::header;
+use std::time::Duration;
+
+const SID_COOKIE: &str = "SID";
+
+/// qBittorrent Web API v2 客户端, SID过期时会重新登录一次
+pub struct QbittorrentClient {
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    http_client: Client,
+    sid: RwLock<Option<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TorrentInfo {
+    pub progress: f64,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TorrentFile {
+    pub index: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Default)]
+pub struct AddTorrent {
+    pub urls: String,
+    pub save_path: String,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub paused: bool,
+}
+
+impl QbittorrentClient {
+    pub fn new(url: &str, username: Option<String>, password: Option<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            username,
+            password,
+            http_client: client,
+            sid: RwLock::new(None),
+        }
+    }
+
+    pub async fn add_torrent(&self, torrent: &AddTorrent) -> Result<(), ProcessingError> {
+        let paused = torrent.paused.to_string();
+        let mut form = vec![
+            ("urls", torrent.urls.clone()),
+            ("savepath", torrent.save_path.clone()),
+            // 5.0之后改名为stopped
+            ("paused", paused.clone()),
+            ("stopped", paused),
+        ];
+        if let Some(category) = &torrent.category {
+            form.push(("category", category.clone()));
+        }
+        if !torrent.tags.is_empty() {
+            form.push(("tags", torrent.tags.join(",")));
+        }
+        let body = self.text("/api/v2/torrents/add", &form).await?;
+        if body.trim() == "Fails." {
+            return Err(ProcessingError::non_retryable(format!(
+                "qBittorrent failed to add torrent {}",
+                torrent.urls
+            )));
+        }
+        Ok(())
+    }
+
+    pub async fn torrent_info(&self, hash: &str) -> Result<Option<TorrentInfo>, ProcessingError> {
+        let response = self
+            .post("/api/v2/torrents/info", &[("hashes", hash.to_string())])
+            .await?;
+        let torrents: Vec<TorrentInfo> = response.json().await.map_err(decode_error)?;
+        Ok(torrents.into_iter().next())
+    }
+
+    pub async fn torrent_files(&self, hash: &str) -> Result<Vec<TorrentFile>, ProcessingError> {
+        let response = self
+            .post("/api/v2/torrents/files", &[("hash", hash.to_string())])
+            .await?;
+        response.json().await.map_err(decode_error)
+    }
+
+    pub async fn set_file_priority(
+        &self,
+        hash: &str,
+        indexes: &[u32],
+        priority: u8,
+    ) -> Result<(), ProcessingError> {
+        let ids = indexes
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>()
+            .join("|");
+        let form = [
+            ("hash", hash.to_string()),
+            ("id", ids),
+            ("priority", priority.to_string()),
+        ];
+        self.post("/api/v2/torrents/filePrio", &form).await?;
+        Ok(())
+    }
+
+    pub async fn resume(&self, hash: &str) -> Result<(), ProcessingError> {
+        let form = [("hashes", hash.to_string())];
+        match self.post("/api/v2/torrents/resume", &form).await {
+            // 5.0之后改名为start
+            Err(e) if e.message().contains("404") => {
+                self.post("/api/v2/torrents/start", &form).await?;
+                Ok(())
+            }
+            result => result.map(|_| ()),
+        }
+    }
+
+    pub async fn delete(&self, hash: &str, delete_files: bool) -> Result<(), ProcessingError> {
+        let form = [
+            ("hashes", hash.to_string()),
+            ("deleteFiles", delete_files.to_string()),
+        ];
+        self.post("/api/v2/torrents/delete", &form).await?;
+        Ok(())
+    }
+
+    async fn text(&self, path: &str, form: &[(&str, String)]) -> Result<String, ProcessingError> {
+        self.post(path, form)
+            .await?
+            .text()
+            .await
+            .map_err(decode_error)
+    }
+
+    async fn post(&self, path: &str, form: &[(&str, String)]) -> Result<Response, ProcessingError> {
+        let sid = self.session(false).await?;
+        let response = self.send(path, form, sid).await?;
+        if response.status() != StatusCode::FORBIDDEN {
+            return check_status(path, response);
+        }
+        let sid = self.session(true).await?;
+        let response = self.send(path, form, sid).await?;
+        check_status(path, response)
+    }
+
+    async fn send(
+        &self,
+        path: &str,
+        form: &[(&str, String)],
+        sid: Option<String>,
+    ) -> Result<Response, ProcessingError> {
+        let mut request = self
+            .http_client
+            .post(format!("{}{}", self.url, path))
+            .form(form);
+        if let Some(sid) = sid {
+            request = request.header(header::COOKIE, format!("{}={}", SID_COOKIE, sid));
+        }
+        request.send().await.map_err(|e| {
+            ProcessingError::retryable(format!("qBittorrent request {} failed: {}", path, e))
+        })
+    }
+
+    /// 没有配置用户名时不登录, 依赖qBittorrent的白名单
+    async fn session(&self, refresh: bool) -> Result<Option<String>, ProcessingError> {
+        let Some(username) = &self.username else {
+            return Ok(None);
+        };
+        if !refresh && let Some(sid) = self.sid.read().clone() {
+            return Ok(Some(sid));
+        }
+        let form = [
+            ("username", username.clone()),
+            ("password", self.password.clone().unwrap_or_default()),
+        ];
+        let response = self.send("/api/v2/auth/login", &form, None).await?;
+        let sid = response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .filter_map(|x| x.to_str().ok())
+            .find_map(|x| {
+                let cookie = x.split(';').next()?;
+                let (name, value) = cookie.split_once('=')?;
+                (name.trim() == SID_COOKIE).then(|| value.to_string())
+            });
+        let body = response.text().await.map_err(decode_error)?;
+        let Some(sid) = sid.filter(|_| body.trim() == "Ok.") else {
+            return Err(ProcessingError::non_retryable(format!(
+                "qBittorrent login failed: {}",
+                body
+            )));
+        };
+        *self.sid.write() = Some(sid.clone());
+        Ok(Some(sid))
+    }
+}
+
+fn check_status(path: &str, response: Response) -> Result<Response, ProcessingError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let message = format!("qBittorrent request {} failed with status {}", path, status);
+    if status.is_server_error() {
+        Err(ProcessingError::retryable(message))
+    } else {
+        Err(ProcessingError::non_retryable(message))
+    }
+}
+
+fn decode_error(e: reqwest::Error) -> ProcessingError {
+    ProcessingError::retryable(format!("Failed to read qBittorrent response: {}", e))
+}
@@ -0,0 +1,159 @@
+use parking_lot::RwLock;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use source_downloader_sdk::component::ProcessingError;
+use source_downloader_sdk::serde_json;
+use source_downloader_sdk::serde_json::{Value, json};
+use std::time::Duration;
+
+const SESSION_HEADER: &str = "X-Transmission-Session-Id";
+
+/// Transmission RPC 客户端, 收到409时使用响应中的session id重试一次
+pub struct TransmissionClient {
+    url: String,
+    username: Option<String>,
+    password: Option<String>,
+    http_client: Client,
+    session_id: RwLock<Option<String>>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Torrent {
+    #[serde(default)]
+    pub percent_done: f64,
+    /// 下标即为文件的index
+    #[serde(default)]
+    pub files: Vec<TorrentFile>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TorrentFile {
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct RpcResponse {
+    result: String,
+    #[serde(default)]
+    arguments: Value,
+}
+
+impl TransmissionClient {
+    pub fn new(url: &str, username: Option<String>, password: Option<String>) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .unwrap_or_default();
+        Self {
+            url: url.to_string(),
+            username,
+            password,
+            http_client: client,
+            session_id: RwLock::new(None),
+        }
+    }
+
+    /// 返回种子的hash, 种子已存在时返回已存在的种子
+    pub async fn add_torrent(&self, arguments: Value) -> Result<String, ProcessingError> {
+        let result = self.call("torrent-add", arguments).await?;
+        let torrent = result
+            .get("torrent-added")
+            .or_else(|| result.get("torrent-duplicate"))
+            .and_then(|x| x.get("hashString"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                ProcessingError::retryable(format!("Invalid torrent-add response {}", result))
+            })?;
+        Ok(torrent.to_string())
+    }
+
+    pub async fn torrent_get(
+        &self,
+        hash: &str,
+        fields: &[&str],
+    ) -> Result<Option<Torrent>, ProcessingError> {
+        let result = self
+            .call("torrent-get", json!({"ids": [hash], "fields": fields}))
+            .await?;
+        let torrents: Vec<Torrent> =
+            serde_json::from_value(result.get("torrents").cloned().unwrap_or_default())
+                .map_err(|e| ProcessingError::retryable(format!("Invalid torrent-get: {}", e)))?;
+        Ok(torrents.into_iter().next())
+    }
+
+    pub async fn set_files_wanted(
+        &self,
+        hash: &str,
+        wanted: &[usize],
+        unwanted: &[usize],
+    ) -> Result<(), ProcessingError> {
+        let mut arguments = json!({"ids": [hash]});
+        // 空数组在Transmission中表示所有文件
+        if !wanted.is_empty() {
+            arguments["files-wanted"] = json!(wanted);
+        }
+        if !unwanted.is_empty() {
+            arguments["files-unwanted"] = json!(unwanted);
+        }
+        self.call("torrent-set", arguments).await?;
+        Ok(())
+    }
+
+    pub async fn start(&self, hash: &str) -> Result<(), ProcessingError> {
+        self.call("torrent-start", json!({"ids": [hash]})).await?;
+        Ok(())
+    }
+
+    pub async fn remove(&self, hash: &str, delete_data: bool) -> Result<(), ProcessingError> {
+        let arguments = json!({"ids": [hash], "delete-local-data": delete_data});
+        self.call("torrent-remove", arguments).await?;
+        Ok(())
+    }
+
+    async fn call(&self, method: &str, arguments: Value) -> Result<Value, ProcessingError> {
+        let body = json!({"method": method, "arguments": arguments});
+        let mut response = self.send(method, &body).await?;
+        if response.status() == StatusCode::CONFLICT {
+            let session_id = response
+                .headers()
+                .get(SESSION_HEADER)
+                .and_then(|x| x.to_str().ok())
+                .map(str::to_string);
+            *self.session_id.write() = session_id;
+            response = self.send(method, &body).await?;
+        }
+        let status = response.status();
+        if !status.is_success() {
+            let message = format!("Transmission {} failed with status {}", method, status);
+            return Err(if status.is_server_error() {
+                ProcessingError::retryable(message)
+            } else {
+                ProcessingError::non_retryable(message)
+            });
+        }
+        let response: RpcResponse = response.json().await.map_err(|e| {
+            ProcessingError::retryable(format!("Invalid Transmission {} response: {}", method, e))
+        })?;
+        if response.result != "success" {
+            return Err(ProcessingError::non_retryable(format!(
+                "Transmission {} error: {}",
+                method, response.result
+            )));
+        }
+        Ok(response.arguments)
+    }
+
+    async fn send(&self, method: &str, body: &Value) -> Result<reqwest::Response, ProcessingError> {
+        let mut request = self.http_client.post(&self.url).json(body);
+        if let Some(username) = &self.username {
+            request = request.basic_auth(username, self.password.as_ref());
+        }
+        if let Some(session_id) = self.session_id.read().as_ref() {
+            request = request.header(SESSION_HEADER, session_id);
+        }
+        request.send().await.map_err(|e| {
+            ProcessingError::retryable(format!("Transmission {} failed: {}", method, e))
+        })
+    }
+}
@@ -2,7 +2,9 @@ mod component;
 mod instance;
 pub mod util;
 
-use crate::component::mikan_source;
+use crate::component::{
+    aria2_downloader, mikan_source, qbittorrent_downloader, transmission_downloader,
+};
 use source_downloader_sdk::component::ComponentSupplier;
 use source_downloader_sdk::instance::InstanceFactory;
 use source_downloader_sdk::plugin::{Plugin, PluginContext, PluginDescription};
@@ -21,7 +23,12 @@ impl Plugin for CommonPlugin {
     }
 
     fn get_component_suppliers(&self) -> Vec<Arc<dyn ComponentSupplier>> {
-        vec![Arc::new(mikan_source::SUPPLIER)]
+        vec![
+            Arc::new(mikan_source::SUPPLIER),
+            Arc::new(qbittorrent_downloader::SUPPLIER),
+            Arc::new(aria2_downloader::SUPPLIER),
+            Arc::new(transmission_downloader::SUPPLIER),
+        ]
     }
 
     fn description(&self) -> PluginDescription {
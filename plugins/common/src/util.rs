@@ -1,3 +1,4 @@
+use source_downloader_sdk::SourceItem;
 use source_downloader_sdk::async_trait::async_trait;
 use source_downloader_sdk::component::ProcessingError;
 use source_downloader_sdk::http::Uri;
@@ -212,3 +213,46 @@ pub fn parse_rfc2822_datetime(
     let dt: OffsetDateTime = OffsetDateTime::parse(date_str, &well_known::Rfc2822)?;
     Ok(dt)
 }
+
+/// 种子的info hash, 依次从attrs中的`torrent-hash`, 磁力链接的btih, 以hash命名的种子链接(如mikan)中获取
+pub fn torrent_hash(item: &SourceItem) -> Option<String> {
+    if let Some(hash) = item.attrs.get("torrent-hash").and_then(|x| x.as_str()) {
+        return Some(hash.to_lowercase());
+    }
+    let uri = item.download_uri.to_string();
+    if let Some((_, rest)) = uri.split_once("xt=urn:btih:") {
+        let btih = rest.split('&').next().unwrap_or_default();
+        return match btih.len() {
+            40 if is_hex(btih) => Some(btih.to_lowercase()),
+            32 => base32_to_hex(btih),
+            _ => None,
+        };
+    }
+    let file_name = item.download_uri.path().rsplit('/').next()?;
+    let stem = file_name.strip_suffix(".torrent").unwrap_or(file_name);
+    (stem.len() == 40 && is_hex(stem)).then(|| stem.to_lowercase())
+}
+
+fn is_hex(s: &str) -> bool {
+    s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn base32_to_hex(s: &str) -> Option<String> {
+    let mut bits: u64 = 0;
+    let mut bit_count = 0;
+    let mut hex = String::with_capacity(40);
+    for c in s.chars() {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return None,
+        };
+        bits = (bits << 5) | value;
+        bit_count += 5;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            hex.push_str(&format!("{:02x}", (bits >> bit_count) & 0xff));
+        }
+    }
+    Some(hex)
+}
@@ -4,6 +4,7 @@ use source_downloader_sdk::component::{
 };
 use source_downloader_sdk::serde_json::{Map, Value};
 use source_downloader_sdk::SdComponent;
+use std::collections::HashMap;
 use std::fmt::{Debug, Display, Formatter};
 use std::sync::{Arc, Mutex};
 
@@ -98,13 +99,32 @@ impl Trigger for FixedScheduleTrigger {
                 interval_timer.tick().await;
             }
 
+            // 同一个组的任务在上一轮还没执行完时跳过本轮
+            let mut group_locks: HashMap<String, Arc<tokio::sync::Mutex<()>>> = HashMap::new();
             loop {
                 interval_timer.tick().await;
-                for task in tasks.read().clone() {
-                    //TODO grouping tasks, then run them in parallel await task.execute()
+                let current = tasks.read().clone();
+                for (group, group_tasks) in group_tasks(current) {
+                    let Some(group) = group else {
+                        for task in group_tasks {
+                            tokio::spawn(async move {
+                                let result = task.run().await;
+                                debug!("Task {} finished with result {:?}", task.name(), result);
+                            });
+                        }
+                        continue;
+                    };
+                    let lock = group_locks.entry(group.clone()).or_default().clone();
+                    let Ok(guard) = lock.try_lock_owned() else {
+                        info!("Task group {} is still running, skipped", group);
+                        continue;
+                    };
                     tokio::spawn(async move {
-                        let result = task.run().await;
-                        debug!("Task {} finished with result {:?}", task.name(), result);
+                        let _guard = guard;
+                        for task in group_tasks {
+                            let result = task.run().await;
+                            debug!("Task {} finished with result {:?}", task.name(), result);
+                        }
                     });
                 }
             }
@@ -144,6 +164,24 @@ impl Trigger for FixedScheduleTrigger {
     }
 }
 
+type TaskGroup = (Option<String>, Vec<Arc<dyn ProcessTask>>);
+
+/// 按注册顺序分组, 同组的任务依次执行, 不同组及没有分组的任务并行执行
+fn group_tasks(tasks: Vec<Arc<dyn ProcessTask>>) -> Vec<TaskGroup> {
+    let mut groups: Vec<TaskGroup> = vec![];
+    for task in tasks {
+        let group = task.group();
+        match groups
+            .iter_mut()
+            .find(|(g, _)| group.is_some() && *g == group)
+        {
+            Some((_, group_tasks)) => group_tasks.push(task),
+            None => groups.push((group, vec![task])),
+        }
+    }
+    groups
+}
+
 impl Debug for FixedScheduleTrigger {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FixedScheduleTrigger")
@@ -197,6 +235,47 @@ mod tests {
     struct TestTask {
         counter: Arc<AtomicUsize>,
     }
+
+    // 记录同时运行的最大任务数
+    struct GroupTask {
+        group: Option<String>,
+        running: Arc<AtomicUsize>,
+        max_running: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl ProcessTask for GroupTask {
+        async fn run(&self) -> Result<(), String> {
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn name(&self) -> &str {
+            "GroupTask"
+        }
+
+        fn group(&self) -> Option<String> {
+            self.group.clone()
+        }
+    }
+
+    fn run_group_tasks(groups: &[Option<&str>]) -> (FixedScheduleTrigger, Arc<AtomicUsize>) {
+        let trigger = FixedScheduleTrigger::new(Duration::from_secs(10), true);
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        for group in groups {
+            trigger.add_task(Arc::new(GroupTask {
+                group: group.map(str::to_string),
+                running: running.clone(),
+                max_running: max_running.clone(),
+            }));
+        }
+        trigger.start();
+        (trigger, max_running)
+    }
     #[async_trait]
     impl ProcessTask for TestTask {
         async fn run(&self) -> Result<(), String> {
@@ -363,4 +442,47 @@ mod tests {
         assert!(c1 > c2, "Task 1 should have run more times than Task 2");
         assert!(c2 > 0, "Task 2 should have executed at least once");
     }
+
+    #[tokio::test]
+    async fn test_same_group_run_sequentially() {
+        let (trigger, max_running) =
+            run_group_tasks(&[Some("mikan"), Some("mikan"), Some("mikan")]);
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        trigger.stop();
+        assert_eq!(max_running.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_different_groups_run_in_parallel() {
+        let (trigger, max_running) = run_group_tasks(&[Some("mikan"), Some("indexer"), None, None]);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        trigger.stop();
+        assert_eq!(max_running.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn test_group_tasks() {
+        let task = |group: Option<&str>| -> Arc<dyn ProcessTask> {
+            Arc::new(GroupTask {
+                group: group.map(str::to_string),
+                running: Arc::new(AtomicUsize::new(0)),
+                max_running: Arc::new(AtomicUsize::new(0)),
+            })
+        };
+        let groups = group_tasks(vec![
+            task(Some("a")),
+            task(None),
+            task(Some("b")),
+            task(Some("a")),
+            task(None),
+        ]);
+        let sizes = groups
+            .iter()
+            .map(|(g, t)| (g.as_deref(), t.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            vec![(Some("a"), 2), (None, 1), (Some("b"), 1), (None, 1)]
+        );
+    }
 }
@@ -1,12 +1,24 @@
 use async_trait::async_trait;
+use futures_util::future::try_join_all;
+use md5::Md5;
+use reqwest::StatusCode;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, RANGE};
 use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use source_downloader_sdk::component::{
     ComponentError, ComponentSupplier, ComponentType, DownloadTask, Downloader, ProcessingError,
     SdComponent, SdComponentMetadata, SourceFile,
 };
 use source_downloader_sdk::{SdComponent, SourceItem};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::fs::OpenOptions;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
 
 pub struct HttpDownloaderSupplier;
 pub const SUPPLIER: HttpDownloaderSupplier = HttpDownloaderSupplier {};
@@ -17,8 +29,27 @@ impl ComponentSupplier for HttpDownloaderSupplier {
     }
 
     fn apply(&self, props: &Map<String, Value>) -> Result<Arc<dyn SdComponent>, ComponentError> {
+        let path = props
+            .get("download-path")
+            .ok_or_else(|| ComponentError::from("Missing 'download-path' property"))?
+            .as_str()
+            .ok_or_else(|| ComponentError::from("Invalid 'download-path' property"))?;
+        let max_concurrency = match props.get("max-concurrency") {
+            None => 4,
+            Some(v) => v.as_u64().filter(|x| *x > 0).ok_or_else(|| {
+                ComponentError::from(
+                    "Invalid 'max-concurrency' property, must be a positive integer",
+                )
+            })?,
+        };
+        let client = reqwest::Client::builder()
+            .build()
+            .map_err(|e| ComponentError::new(e.to_string()))?;
         Ok(Arc::new(HttpDownloader {
-            path: props["download-path"].to_string(),
+            path: path.to_string(),
+            client,
+            semaphore: Semaphore::new(max_concurrency as usize),
+            active: parking_lot::Mutex::new(HashMap::new()),
         }))
     }
 
@@ -27,11 +58,16 @@ impl ComponentSupplier for HttpDownloaderSupplier {
     }
 }
 
+/// 下载到`{path}.part`, 完成并校验后重命名为目标文件, 已存在的`.part`文件使用Range请求续传
 #[derive(SdComponent, Debug)]
 #[component(Downloader)]
-#[allow(dead_code, unused)]
 struct HttpDownloader {
     path: String,
+    client: reqwest::Client,
+    // 限制所有Item的同时下载的文件数
+    semaphore: Semaphore,
+    // 下载中的Item, key为item hash
+    active: parking_lot::Mutex<HashMap<String, CancellationToken>>,
 }
 
 impl Display for HttpDownloader {
@@ -40,11 +76,42 @@ impl Display for HttpDownloader {
     }
 }
 
-#[allow(dead_code, unused)]
 #[async_trait]
 impl Downloader for HttpDownloader {
     async fn submit(&self, task: &DownloadTask) -> Result<(), ProcessingError> {
-        todo!()
+        if task.download_files.is_empty() {
+            return Ok(());
+        }
+        let headers = to_header_map(task.headers.as_ref())?;
+        let mut downloads = vec![];
+        for file in task.download_files {
+            let uri = match file.download_uri {
+                Some(uri) => uri,
+                None if task.download_files.len() == 1 => &task.source_item.download_uri,
+                None => {
+                    return Err(ProcessingError::non_retryable(format!(
+                        "File {} has no download uri",
+                        file.path.display()
+                    )));
+                }
+            };
+            let path = if file.path.is_absolute() {
+                file.path.to_path_buf()
+            } else {
+                task.download_path.join(file.path)
+            };
+            downloads.push((uri.to_string(), path, Checksum::parse(file.attrs)?));
+        }
+
+        let item_hash = task.source_item.hashing();
+        let token = CancellationToken::new();
+        self.active.lock().insert(item_hash.clone(), token.clone());
+        let result = try_join_all(downloads.iter().map(|(uri, path, checksum)| {
+            self.download_file(uri, path, &headers, checksum.as_ref(), &token)
+        }))
+        .await;
+        self.active.lock().remove(&item_hash);
+        result.map(|_| ())
     }
 
     fn default_download_path(&self) -> &str {
@@ -52,6 +119,382 @@ impl Downloader for HttpDownloader {
     }
 
     async fn cancel(&self, item: &SourceItem, files: &[SourceFile]) -> Result<(), ProcessingError> {
-        todo!()
+        if let Some(token) = self.active.lock().remove(&item.hashing()) {
+            token.cancel();
+        }
+        for file in files {
+            for path in [part_path(&file.path), file.path.clone()] {
+                match tokio::fs::remove_file(&path).await {
+                    Ok(_) => debug!("[http-cancel] removed {}", path.display()),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl HttpDownloader {
+    async fn download_file(
+        &self,
+        uri: &str,
+        path: &Path,
+        headers: &HeaderMap,
+        checksum: Option<&Checksum>,
+        token: &CancellationToken,
+    ) -> Result<(), ProcessingError> {
+        let _permit = tokio::select! {
+            permit = self.semaphore.acquire() => permit
+                .map_err(|e| ProcessingError::non_retryable(e.to_string()))?,
+            _ = token.cancelled() => return Err(cancelled(uri)),
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let part = part_path(path);
+        let offset = match tokio::fs::metadata(&part).await {
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        };
+        let mut request = self.client.get(uri).headers(headers.clone());
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send().await.map_err(|e| request_error(uri, e))?;
+        let append = match response.status() {
+            StatusCode::PARTIAL_CONTENT => true,
+            // 已下载的部分就是完整的文件
+            StatusCode::RANGE_NOT_SATISFIABLE if offset > 0 => {
+                return complete_file(&part, path, checksum).await;
+            }
+            status if status.is_success() => false,
+            status if status.is_server_error() => {
+                return Err(ProcessingError::retryable(format!(
+                    "Download {} failed with status {}",
+                    uri, status
+                )));
+            }
+            status => {
+                return Err(ProcessingError::non_retryable(format!(
+                    "Download {} failed with status {}",
+                    uri, status
+                )));
+            }
+        };
+        if append {
+            info!("[http-resume] {} from {} bytes", uri, offset);
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&part)
+            .await?;
+        loop {
+            let chunk = tokio::select! {
+                chunk = response.chunk() => chunk.map_err(|e| request_error(uri, e))?,
+                _ = token.cancelled() => return Err(cancelled(uri)),
+            };
+            let Some(chunk) = chunk else {
+                break;
+            };
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        drop(file);
+        complete_file(&part, path, checksum).await
+    }
+}
+
+#[derive(Debug)]
+enum Checksum {
+    Sha256(String),
+    Md5(String),
+}
+
+impl Checksum {
+    /// 文件attrs中的`checksum`, 格式为`sha256:<hex>`或`md5:<hex>`
+    fn parse(attrs: &Map<String, Value>) -> Result<Option<Checksum>, ProcessingError> {
+        let Some(value) = attrs.get("checksum").and_then(Value::as_str) else {
+            return Ok(None);
+        };
+        let checksum = match value.split_once(':') {
+            Some(("sha256", hex)) => Checksum::Sha256(hex.to_lowercase()),
+            Some(("md5", hex)) => Checksum::Md5(hex.to_lowercase()),
+            _ => {
+                return Err(ProcessingError::non_retryable(format!(
+                    "Unsupported checksum {}",
+                    value
+                )));
+            }
+        };
+        Ok(Some(checksum))
+    }
+
+    async fn verify(&self, path: &Path) -> Result<bool, ProcessingError> {
+        let actual = match self {
+            Checksum::Sha256(_) => digest_file::<Sha256>(path).await?,
+            Checksum::Md5(_) => digest_file::<Md5>(path).await?,
+        };
+        let expected = match self {
+            Checksum::Sha256(hex) | Checksum::Md5(hex) => hex,
+        };
+        Ok(actual == *expected)
+    }
+}
+
+async fn digest_file<D: Digest>(path: &Path) -> Result<String, ProcessingError> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = D::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 校验失败时删除已下载的部分, 重试时重新下载
+async fn complete_file(
+    part: &Path,
+    path: &Path,
+    checksum: Option<&Checksum>,
+) -> Result<(), ProcessingError> {
+    if let Some(checksum) = checksum
+        && !checksum.verify(part).await?
+    {
+        tokio::fs::remove_file(part).await?;
+        return Err(ProcessingError::retryable(format!(
+            "Checksum mismatch {} expected {:?}",
+            path.display(),
+            checksum
+        )));
+    }
+    tokio::fs::rename(part, path).await?;
+    debug!("[http-downloaded] {}", path.display());
+    Ok(())
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    path.with_file_name(name)
+}
+
+fn to_header_map(
+    headers: Option<&HashMap<&String, &String>>,
+) -> Result<HeaderMap, ProcessingError> {
+    let mut header_map = HeaderMap::new();
+    for (k, v) in headers.into_iter().flatten() {
+        let name = HeaderName::try_from(k.as_str())
+            .map_err(|e| ProcessingError::non_retryable(format!("Invalid header {}: {}", k, e)))?;
+        let value = HeaderValue::try_from(v.as_str())
+            .map_err(|e| ProcessingError::non_retryable(format!("Invalid header {}: {}", k, e)))?;
+        header_map.insert(name, value);
+    }
+    Ok(header_map)
+}
+
+fn request_error(uri: &str, err: reqwest::Error) -> ProcessingError {
+    ProcessingError::retryable(format!("Download {} failed: {}", uri, err))
+}
+
+fn cancelled(uri: &str) -> ProcessingError {
+    ProcessingError::non_retryable(format!("Download {} cancelled", uri))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use axum::Router;
+    use axum::http::{HeaderMap as AxumHeaderMap, StatusCode as AxumStatusCode};
+    use axum::response::IntoResponse;
+    use axum::routing::get;
+    use source_downloader_sdk::component::SourceFileRef;
+    use source_downloader_sdk::time::OffsetDateTime;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    const CONTENT: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    struct TestServer {
+        addr: String,
+        range_requested: Arc<AtomicBool>,
+    }
+
+    async fn start_server() -> TestServer {
+        let range_requested = Arc::new(AtomicBool::new(false));
+        let flag = range_requested.clone();
+        let app = Router::new().route(
+            "/file",
+            get(move |headers: AxumHeaderMap| async move {
+                if headers.get("x-token").is_none_or(|x| x != "secret") {
+                    return AxumStatusCode::FORBIDDEN.into_response();
+                }
+                let Some(range) = headers.get("range") else {
+                    return CONTENT.into_response();
+                };
+                flag.store(true, Ordering::SeqCst);
+                let start: usize = range
+                    .to_str()
+                    .unwrap()
+                    .trim_start_matches("bytes=")
+                    .trim_end_matches('-')
+                    .parse()
+                    .unwrap();
+                if start >= CONTENT.len() {
+                    return AxumStatusCode::RANGE_NOT_SATISFIABLE.into_response();
+                }
+                (AxumStatusCode::PARTIAL_CONTENT, &CONTENT[start..]).into_response()
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        TestServer {
+            addr,
+            range_requested,
+        }
+    }
+
+    fn downloader(dir: &Path) -> Arc<dyn SdComponent> {
+        let mut props = Map::new();
+        props.insert(
+            "download-path".to_string(),
+            Value::String(dir.to_string_lossy().to_string()),
+        );
+        SUPPLIER.apply(&props).unwrap()
+    }
+
+    fn source_item(uri: &str) -> SourceItem {
+        SourceItem {
+            title: "test".to_string(),
+            link: uri.parse().unwrap(),
+            datetime: OffsetDateTime::now_utc(),
+            content_type: "application/octet-stream".to_string(),
+            download_uri: uri.parse().unwrap(),
+            attrs: Default::default(),
+            tags: Default::default(),
+            identity: None,
+        }
+    }
+
+    async fn submit(
+        downloader: &Arc<dyn SdComponent>,
+        item: &SourceItem,
+        dir: &Path,
+        attrs: &Map<String, Value>,
+    ) -> Result<(), ProcessingError> {
+        let path = PathBuf::from("a/test.bin");
+        let files = vec![SourceFileRef {
+            path: &path,
+            attrs,
+            download_uri: None,
+            tags: &[],
+            data: None,
+        }];
+        let token_key = "x-token".to_string();
+        let token_value = "secret".to_string();
+        let task = DownloadTask {
+            source_item: item,
+            download_files: &files,
+            download_path: dir,
+            category: &None,
+            tags: None,
+            headers: Some(HashMap::from([(&token_key, &token_value)])),
+        };
+        downloader
+            .clone()
+            .as_downloader()
+            .unwrap()
+            .submit(&task)
+            .await
+    }
+
+    #[test]
+    fn download_path_prop() {
+        let downloader = downloader(Path::new("/downloads"));
+        let downloader = downloader.as_downloader().unwrap();
+        assert_eq!(downloader.default_download_path(), "/downloads");
+    }
+
+    #[tokio::test]
+    async fn download_with_checksum() {
+        let server = start_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = downloader(dir.path());
+        let item = source_item(&format!("{}/file", server.addr));
+        let mut attrs = Map::new();
+        attrs.insert(
+            "checksum".to_string(),
+            Value::String(format!("sha256:{}", hex::encode(Sha256::digest(CONTENT)))),
+        );
+        submit(&downloader, &item, dir.path(), &attrs)
+            .await
+            .unwrap();
+        let target = dir.path().join("a/test.bin");
+        assert_eq!(std::fs::read(&target).unwrap(), CONTENT);
+        assert!(!part_path(&target).exists());
+        assert!(!server.range_requested.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn resume_partial_file() {
+        let server = start_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("a/test.bin");
+        std::fs::create_dir_all(target.parent().unwrap()).unwrap();
+        std::fs::write(part_path(&target), &CONTENT[..10]).unwrap();
+
+        let downloader = downloader(dir.path());
+        let item = source_item(&format!("{}/file", server.addr));
+        let mut attrs = Map::new();
+        attrs.insert(
+            "checksum".to_string(),
+            Value::String(format!("md5:{}", hex::encode(Md5::digest(CONTENT)))),
+        );
+        submit(&downloader, &item, dir.path(), &attrs)
+            .await
+            .unwrap();
+        assert!(server.range_requested.load(Ordering::SeqCst));
+        assert_eq!(std::fs::read(&target).unwrap(), CONTENT);
+    }
+
+    #[tokio::test]
+    async fn checksum_mismatch() {
+        let server = start_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = downloader(dir.path());
+        let item = source_item(&format!("{}/file", server.addr));
+        let mut attrs = Map::new();
+        attrs.insert(
+            "checksum".to_string(),
+            Value::String("sha256:0000".to_string()),
+        );
+        let err = submit(&downloader, &item, dir.path(), &attrs)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProcessingError::Retryable { .. }));
+        let target = dir.path().join("a/test.bin");
+        assert!(!target.exists());
+        assert!(!part_path(&target).exists());
+    }
+
+    #[tokio::test]
+    async fn client_error_is_non_retryable() {
+        let server = start_server().await;
+        let dir = tempfile::tempdir().unwrap();
+        let downloader = downloader(dir.path());
+        let item = source_item(&format!("{}/missing", server.addr));
+        let err = submit(&downloader, &item, dir.path(), &Map::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ProcessingError::NonRetryable { .. }));
+        assert!(err.message().contains("404"));
     }
 }
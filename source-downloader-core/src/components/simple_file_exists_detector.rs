@@ -34,7 +34,7 @@ impl ComponentSupplier for SimpleFileExistsDetectorSupplier {
 #[derive(SdComponent, Debug)]
 #[component(FileExistsDetector)]
 #[allow(dead_code, unused)]
-struct SimpleFileExistsDetector {}
+pub struct SimpleFileExistsDetector {}
 
 impl Display for SimpleFileExistsDetector {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
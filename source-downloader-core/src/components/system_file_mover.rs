@@ -1,24 +1,38 @@
+use serde::Deserialize;
 use serde_json::{Map, Value};
 use source_downloader_sdk::SdComponent;
+use source_downloader_sdk::component::FileContentStatus::ReadyReplace;
 use source_downloader_sdk::component::{
     ComponentError, ComponentSupplier, ComponentType, FileMover, ItemContent, ProcessingError,
     SdComponent, SdComponentMetadata, SourceFile,
 };
 use std::fmt::{Display, Formatter};
-use std::path::PathBuf;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::UNIX_EPOCH;
+use std::{fs, io};
+use tracing::{debug, warn};
 
 pub struct SystemFileMoverSupplier {}
 pub const SUPPLIER: SystemFileMoverSupplier = SystemFileMoverSupplier {};
-const INSTANCE: SystemFileMover = SystemFileMover {};
 
 impl ComponentSupplier for SystemFileMoverSupplier {
     fn supply_types(&self) -> Vec<ComponentType> {
         vec![ComponentType::file_mover("system-file".to_owned())]
     }
 
-    fn apply(&self, _: &Map<String, Value>) -> Result<Arc<dyn SdComponent>, ComponentError> {
-        Ok(Arc::new(INSTANCE))
+    fn apply(&self, props: &Map<String, Value>) -> Result<Arc<dyn SdComponent>, ComponentError> {
+        let mode = match props.get("mode") {
+            None => MoveMode::Move,
+            Some(v) => MoveMode::deserialize(v).map_err(|_| {
+                ComponentError::new(format!(
+                    "Invalid 'mode' property {}, expected one of move, copy, hardlink, symlink, reflink",
+                    v
+                ))
+            })?,
+        };
+        Ok(Arc::new(SystemFileMover { mode }))
     }
 
     fn is_support_no_props(&self) -> bool {
@@ -30,9 +44,24 @@ impl ComponentSupplier for SystemFileMoverSupplier {
     }
 }
 
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+enum MoveMode {
+    /// 跨设备时退化为复制后删除
+    Move,
+    Copy,
+    /// 源文件保留, 下载器可以继续做种
+    Hardlink,
+    Symlink,
+    /// 写时复制, 仅支持Linux上的btrfs/xfs等文件系统
+    Reflink,
+}
+
 #[derive(SdComponent, Debug)]
 #[component(FileMover)]
-struct SystemFileMover {}
+struct SystemFileMover {
+    mode: MoveMode,
+}
 
 impl Display for SystemFileMover {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -40,33 +69,356 @@ impl Display for SystemFileMover {
     }
 }
 
-#[allow(dead_code, unused)]
 impl FileMover for SystemFileMover {
     fn move_file(
         &self,
         source_file: &SourceFile,
-        download_path: &str,
+        target_path: &str,
     ) -> Result<(), ProcessingError> {
-        todo!()
+        let target = Path::new(target_path);
+        self.transfer(&source_file.path, target).map_err(|e| {
+            ProcessingError::non_retryable(format!(
+                "Failed to {:?} {} to {}: {}",
+                self.mode,
+                source_file.path.display(),
+                target_path,
+                e
+            ))
+        })
     }
 
     fn exists(&self, path: &Vec<&PathBuf>) -> Vec<bool> {
-        todo!()
+        // 失效的软链接也占用了路径
+        path.iter()
+            .map(|p| fs::symlink_metadata(p).is_ok())
+            .collect()
     }
 
     fn create_directories(&self, path: &str) -> Result<(), ProcessingError> {
-        todo!()
+        fs::create_dir_all(path)?;
+        Ok(())
     }
 
+    /// 先把已存在的文件备份, 失败时还原
     fn replace(&self, item_content: &ItemContent) -> Result<(), ProcessingError> {
-        todo!()
+        for file in item_content
+            .file_contents
+            .iter()
+            .filter(|f| f.status == ReadyReplace)
+        {
+            let target = file.target_path();
+            let mut backup_name = target.file_name().unwrap_or_default().to_os_string();
+            backup_name.push(".bak");
+            let backup = target.with_file_name(backup_name);
+            fs::rename(target, &backup)?;
+            if let Err(e) = self.transfer(&file.file_download_path, target) {
+                if let Err(restore) = fs::rename(&backup, target) {
+                    warn!("Failed to restore {} cause={}", target.display(), restore);
+                }
+                return Err(ProcessingError::non_retryable(format!(
+                    "Failed to replace {}: {}",
+                    target.display(),
+                    e
+                )));
+            }
+            fs::remove_file(&backup)?;
+        }
+        Ok(())
     }
 
     fn list_files(&self, path: &str) -> Vec<String> {
-        todo!()
+        let Ok(entries) = fs::read_dir(path) else {
+            return vec![];
+        };
+        let mut files = entries
+            .filter_map(Result::ok)
+            .map(|e| e.path().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+
+    fn is_source_retained(&self) -> bool {
+        self.mode != MoveMode::Move
+    }
+
+    fn delete_file(&self, path: &str) -> Result<(), ProcessingError> {
+        fs::remove_file(path).map_err(|e| {
+            ProcessingError::non_retryable(format!("Failed to delete {}: {}", path, e))
+        })
     }
 
     fn path_metadata(&self, path: &str) -> SourceFile {
-        todo!()
+        let mut file = SourceFile::new(PathBuf::from(path));
+        if let Ok(metadata) = fs::metadata(path) {
+            file.attrs
+                .insert("size".to_string(), Value::from(metadata.len()));
+            if let Some(modified) = metadata
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            {
+                file.attrs
+                    .insert("last-modified".to_string(), Value::from(modified.as_secs()));
+            }
+        }
+        file
+    }
+}
+
+impl SystemFileMover {
+    /// 所有模式在目标已存在时都失败, 替换只通过replace进行
+    fn transfer(&self, source: &Path, target: &Path) -> io::Result<()> {
+        match self.mode {
+            MoveMode::Move => {
+                // rename会覆盖已存在的文件
+                if fs::symlink_metadata(target).is_ok() {
+                    return Err(io::Error::new(
+                        ErrorKind::AlreadyExists,
+                        "target already exists",
+                    ));
+                }
+                match fs::rename(source, target) {
+                    Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+                        debug!(
+                            "[cross-device] {} -> {}, fallback to copy",
+                            source.display(),
+                            target.display()
+                        );
+                        copy_then_remove(source, target)
+                    }
+                    result => result,
+                }
+            }
+            MoveMode::Copy => copy_new(source, target),
+            MoveMode::Hardlink => fs::hard_link(source, target),
+            MoveMode::Symlink => symlink(source, target),
+            MoveMode::Reflink => reflink(source, target),
+        }
+    }
+}
+
+/// 跨设备时rename不可用, 复制完成后再删除源文件
+fn copy_then_remove(source: &Path, target: &Path) -> io::Result<()> {
+    copy_new(source, target)?;
+    fs::remove_file(source)
+}
+
+/// 和fs::copy不同, 目标已存在时失败
+fn copy_new(source: &Path, target: &Path) -> io::Result<()> {
+    let mut source_file = fs::File::open(source)?;
+    let permissions = source_file.metadata()?.permissions();
+    let mut target_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+    let result = io::copy(&mut source_file, &mut target_file)
+        .and_then(|_| target_file.set_permissions(permissions));
+    if result.is_err() {
+        drop(target_file);
+        let _ = fs::remove_file(target);
+    }
+    result
+}
+
+#[cfg(unix)]
+fn symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(source, target)
+}
+
+#[cfg(windows)]
+fn symlink(source: &Path, target: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(source, target)
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(source: &Path, target: &Path) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    // _IOW(0x94, 9, int)
+    const FICLONE: libc::c_ulong = 0x40049409;
+
+    let source_file = fs::File::open(source)?;
+    let target_file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+    let ret = unsafe {
+        libc::ioctl(
+            target_file.as_raw_fd(),
+            FICLONE as _,
+            source_file.as_raw_fd(),
+        )
+    };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        drop(target_file);
+        let _ = fs::remove_file(target);
+        return Err(err);
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_: &Path, _: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        ErrorKind::Unsupported,
+        "reflink is only supported on linux",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use source_downloader_sdk::SourceItem;
+    use source_downloader_sdk::component::FileContent;
+    use source_downloader_sdk::storage::ProcessingStatus;
+    use source_downloader_sdk::time::OffsetDateTime;
+
+    fn mover(mode: &str) -> Arc<dyn SdComponent> {
+        let mut props = Map::new();
+        props.insert("mode".to_string(), Value::String(mode.to_string()));
+        SUPPLIER.apply(&props).unwrap()
+    }
+
+    fn move_file(mode: &str, source: &Path, target: &Path) -> Result<(), ProcessingError> {
+        mover(mode).as_file_mover().unwrap().move_file(
+            &SourceFile::new(source.to_path_buf()),
+            &target.to_string_lossy(),
+        )
+    }
+
+    #[test]
+    fn move_modes() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+
+        fs::write(&source, "move").unwrap();
+        let target = dir.path().join("move.txt");
+        move_file("move", &source, &target).unwrap();
+        assert!(!source.exists());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "move");
+
+        fs::write(&source, "copy").unwrap();
+        let target = dir.path().join("copy.txt");
+        move_file("copy", &source, &target).unwrap();
+        assert!(source.exists());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "copy");
+
+        let target = dir.path().join("hardlink.txt");
+        move_file("hardlink", &source, &target).unwrap();
+        fs::write(&source, "seeding").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "seeding");
+
+        let target = dir.path().join("symlink.txt");
+        move_file("symlink", &source, &target).unwrap();
+        assert!(fs::symlink_metadata(&target).unwrap().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "seeding");
+
+        // 目标已存在时不覆盖
+        for mode in ["move", "copy", "hardlink", "symlink"] {
+            assert!(move_file(mode, &source, &target).is_err());
+        }
+        assert!(source.exists());
+        assert!(fs::symlink_metadata(&target).unwrap().is_symlink());
+    }
+
+    #[test]
+    fn cross_device_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source.txt");
+        fs::write(&source, "cross").unwrap();
+        let target = dir.path().join("target.txt");
+        copy_then_remove(&source, &target).unwrap();
+        assert!(!source.exists());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "cross");
+
+        // 目标已存在时保留源文件
+        fs::write(&source, "other").unwrap();
+        assert!(copy_then_remove(&source, &target).is_err());
+        assert!(source.exists());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "cross");
+    }
+
+    #[test]
+    fn invalid_mode() {
+        let mut props = Map::new();
+        props.insert("mode".to_string(), Value::String("teleport".to_string()));
+        assert!(SUPPLIER.apply(&props).is_err());
+        assert!(SUPPLIER.apply(&Map::new()).is_ok());
+    }
+
+    #[test]
+    fn query_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("1.txt");
+        fs::write(&file, "12345").unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let missing = dir.path().join("2.txt");
+
+        let mover = mover("move");
+        let mover = mover.as_file_mover().unwrap();
+        assert_eq!(mover.exists(&vec![&file, &missing]), vec![true, false]);
+        assert_eq!(
+            mover.list_files(&dir.path().to_string_lossy()),
+            vec![
+                file.to_string_lossy().to_string(),
+                dir.path().join("sub").to_string_lossy().to_string()
+            ]
+        );
+        let metadata = mover.path_metadata(&file.to_string_lossy());
+        assert_eq!(metadata.attrs["size"], 5);
+        assert!(metadata.attrs.contains_key("last-modified"));
+    }
+
+    #[test]
+    fn replace_existing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let download = dir.path().join("download.txt");
+        fs::write(&download, "new").unwrap();
+        let target_dir = dir.path().join("target");
+        fs::create_dir(&target_dir).unwrap();
+        fs::write(target_dir.join("1.txt"), "old").unwrap();
+
+        let file_content = FileContent {
+            download_path: dir.path().to_path_buf(),
+            file_download_path: download.clone(),
+            source_save_path: dir.path().to_path_buf(),
+            pattern_variables: Default::default(),
+            tags: vec![],
+            attrs: Default::default(),
+            file_uri: None,
+            target_save_path: target_dir.clone(),
+            target_filename: "1.txt".to_string(),
+            exist_target_path: None,
+            errors: vec![],
+            status: ReadyReplace,
+            target_path: Default::default(),
+            data: None,
+        };
+        let source_item = SourceItem {
+            title: "test".to_string(),
+            link: "file://test".parse().unwrap(),
+            datetime: OffsetDateTime::now_utc(),
+            content_type: "file".to_string(),
+            download_uri: "file://test".parse().unwrap(),
+            attrs: Default::default(),
+            tags: vec![],
+            identity: None,
+        };
+        let file_contents = vec![file_content];
+        let item_content = ItemContent {
+            source_item: &source_item,
+            file_contents: &file_contents,
+            item_variables: &Default::default(),
+            status: ProcessingStatus::WaitingToRename,
+        };
+        mover("hardlink")
+            .as_file_mover()
+            .unwrap()
+            .replace(&item_content)
+            .unwrap();
+        assert_eq!(fs::read_to_string(target_dir.join("1.txt")).unwrap(), "new");
+        assert!(download.exists());
+        assert!(!target_dir.join("1.txt.bak").exists());
     }
 }
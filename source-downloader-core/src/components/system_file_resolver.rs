@@ -2,8 +2,8 @@ use std::fmt::{Display, Formatter};
 use async_trait::async_trait;
 use serde_json::{Map, Value};
 use source_downloader_sdk::component::{
-    ComponentError, ComponentSupplier, ComponentType, ItemFileResolver, SdComponent,
-    SdComponentMetadata, SourceFile,
+    ComponentError, ComponentSupplier, ComponentType, ItemFileResolver, ProcessingError,
+    SdComponent, SdComponentMetadata, SourceFile,
 };
 use source_downloader_sdk::{SdComponent, SourceItem};
 use std::path::PathBuf;
@@ -44,14 +44,17 @@ impl Display for SystemFileResolver {
 
 #[async_trait]
 impl ItemFileResolver for SystemFileResolver {
-    async fn resolve_files(&self, source_item: &SourceItem) -> Vec<SourceFile> {
+    async fn resolve_files(
+        &self,
+        source_item: &SourceItem,
+    ) -> Result<Vec<SourceFile>, ProcessingError> {
         let path = Url::parse(&source_item.download_uri.to_string())
             .unwrap()
             .to_file_path()
             // 可能有问题，中文和前缀没处理
             .unwrap_or_else(|_| PathBuf::from(&source_item.download_uri.to_string()));
         if !path.exists() {
-            return vec![];
+            return Ok(vec![]);
         }
         if path.is_dir() {
             let mut entries: Vec<SourceFile> = WalkDir::new(path)
@@ -61,9 +64,9 @@ impl ItemFileResolver for SystemFileResolver {
                 .map(|e| SourceFile::new(e.into_path()))
                 .collect();
             entries.sort_by(|a, b| a.path.cmp(&b.path));
-            entries
+            Ok(entries)
         } else {
-            vec![SourceFile::new(path)]
+            Ok(vec![SourceFile::new(path)])
         }
     }
 }
@@ -91,7 +94,7 @@ mod tests {
         };
 
         let resolver = INSTANCE;
-        let result = resolver.resolve_files(&item).await;
+        let result = resolver.resolve_files(&item).await.unwrap();
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].path, file_path);
@@ -124,7 +127,7 @@ mod tests {
         };
 
         let resolver = INSTANCE;
-        let result = resolver.resolve_files(&item).await;
+        let result = resolver.resolve_files(&item).await.unwrap();
 
         // 验证结果数量
         assert_eq!(result.len(), 2);
@@ -153,7 +156,7 @@ mod tests {
             ..Default::default()
         };
         let resolver = INSTANCE;
-        let result = resolver.resolve_files(&item).await;
+        let result = resolver.resolve_files(&item).await.unwrap();
 
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].path, file_path);
@@ -172,7 +175,7 @@ mod tests {
         };
 
         let resolver = INSTANCE;
-        let result = resolver.resolve_files(&item).await;
+        let result = resolver.resolve_files(&item).await.unwrap();
 
         assert_eq!(result.len(), 0);
     }
@@ -115,6 +115,15 @@ pub struct ProcessorOptionConfig {
     pub process_listeners: Vec<ListenerConfig>,
     #[serde(skip_serializing_if = "is_default")]
     pub file_exists_detector: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub file_replacement_decider: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub retry: RetryConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub source_health: SourceHealthConfig,
+    /// Item数据准备阶段的超时时间, 为空时不限制
+    #[serde(skip_serializing_if = "is_default")]
+    pub item_timeout: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
@@ -140,10 +149,10 @@ pub struct FileRuleConfig {
     pub file_content_filters: Option<Vec<String>>,
     pub file_content_expression_exclusions: Option<Vec<String>>,
     pub file_content_expression_inclusions: Option<Vec<String>>,
-    // pub file_replacement_decider: Option<String>
+    pub file_replacement_decider: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone, Serialize, PartialEq)]
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq, Eq, Hash)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum ListenerMode {
     Each,
@@ -258,10 +267,64 @@ impl Default for ProcessorOptionConfig {
             download_options: DownloadOptionsConfig::default(),
             process_listeners: vec![],
             file_exists_detector: None,
+            file_replacement_decider: None,
+            retry: RetryConfig::default(),
+            source_health: SourceHealthConfig::default(),
+            item_timeout: None,
         }
     }
 }
 
+/// 未配置的阶段使用顶层的值, 顶层未配置则使用默认值
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RetryConfig {
+    #[serde(flatten)]
+    pub policy: RetryPolicyConfig,
+    #[serde(skip_serializing_if = "is_default")]
+    pub fetch: Option<RetryPolicyConfig>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub resolve: Option<RetryPolicyConfig>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub download: Option<RetryPolicyConfig>,
+    #[serde(rename = "move", skip_serializing_if = "is_default")]
+    pub movement: Option<RetryPolicyConfig>,
+}
+
+/// Source连续获取失败达到阈值后, 触发器的调度会被跳过, 跳过的时长随失败次数指数增长
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct SourceHealthConfig {
+    /// 0表示不跳过
+    pub failure_threshold: u32,
+    pub cooldown: String,
+    pub max_cooldown: String,
+}
+
+impl Default for SourceHealthConfig {
+    fn default() -> Self {
+        SourceHealthConfig {
+            failure_threshold: 3,
+            cooldown: "5m".to_string(),
+            max_cooldown: "6h".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RetryPolicyConfig {
+    /// 包含首次执行
+    #[serde(skip_serializing_if = "is_default")]
+    pub max_attempts: Option<u32>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub base_delay: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub max_delay: Option<String>,
+    #[serde(skip_serializing_if = "is_default")]
+    pub jitter: Option<bool>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(default)]
 pub struct DownloadOptionsConfig {
@@ -0,0 +1,86 @@
+use serde::Serialize;
+use source_downloader_sdk::SourceItem;
+use source_downloader_sdk::time::OffsetDateTime;
+use tokio::sync::broadcast;
+
+/// 处理过程中产生的事件, 用于实时展示处理器的活动
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessEvent {
+    pub processor_name: String,
+    /// 重命名任务产生的事件没有trace_id
+    pub trace_id: Option<String>,
+    #[serde(with = "source_downloader_sdk::time::serde::iso8601")]
+    pub timestamp: OffsetDateTime,
+    #[serde(flatten)]
+    pub kind: ProcessEventKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum ProcessEventKind {
+    RunStarted {
+        trigger_source: String,
+    },
+    ItemFiltered {
+        item: ItemRef,
+        filter: String,
+    },
+    /// 已提交到下载器, 异步下载器此时还未下载完成
+    ItemSubmitted {
+        item: ItemRef,
+    },
+    ItemRenamed {
+        item: ItemRef,
+    },
+    ItemFailed {
+        item: ItemRef,
+        message: String,
+    },
+    RunFinished {
+        fetched_count: u32,
+        processed_count: u32,
+        filtered_count: u32,
+        error: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ItemRef {
+    pub title: String,
+    pub hash: String,
+}
+
+impl From<&SourceItem> for ItemRef {
+    fn from(item: &SourceItem) -> Self {
+        ItemRef {
+            title: item.title.clone(),
+            hash: item.hashing(),
+        }
+    }
+}
+
+/// 没有订阅者时事件直接丢弃, 订阅者消费过慢时会丢失最旧的事件
+pub struct ProcessEventBus {
+    sender: broadcast::Sender<ProcessEvent>,
+}
+
+impl ProcessEventBus {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn publish(&self, event: ProcessEvent) {
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ProcessEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for ProcessEventBus {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
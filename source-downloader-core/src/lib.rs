@@ -2,6 +2,7 @@ pub mod application;
 pub mod component_manager;
 pub mod components;
 pub mod config;
+pub mod event;
 pub mod expression;
 pub mod instance_manager;
 pub mod plugin;
@@ -0,0 +1,122 @@
+use crate::config::SourceHealthConfig;
+use serde::Serialize;
+use source_downloader_sdk::storage::ProcessorSourceState;
+use source_downloader_sdk::time::OffsetDateTime;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceHealthPolicy {
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+    pub max_cooldown: Duration,
+}
+
+impl Default for SourceHealthPolicy {
+    fn default() -> Self {
+        SourceHealthPolicy::try_from(&SourceHealthConfig::default()).unwrap()
+    }
+}
+
+impl SourceHealthPolicy {
+    /// 达到阈值时为cooldown, 之后每多失败一次翻倍, 不超过max_cooldown
+    pub fn cooldown(&self, failures: u32) -> Option<Duration> {
+        if self.failure_threshold == 0 || failures < self.failure_threshold {
+            return None;
+        }
+        let exponent = (failures - self.failure_threshold).min(31);
+        let cooldown = self
+            .cooldown
+            .checked_mul(1 << exponent)
+            .unwrap_or(self.max_cooldown);
+        Some(cooldown.min(self.max_cooldown))
+    }
+}
+
+impl TryFrom<&SourceHealthConfig> for SourceHealthPolicy {
+    type Error = String;
+
+    fn try_from(config: &SourceHealthConfig) -> Result<Self, Self::Error> {
+        let parse = |name: &str, value: &str| {
+            humantime::parse_duration(value)
+                .map_err(|e| format!("source-health {} '{}' is invalid: {}", name, value, e))
+        };
+        Ok(SourceHealthPolicy {
+            failure_threshold: config.failure_threshold,
+            cooldown: parse("cooldown", &config.cooldown)?,
+            max_cooldown: parse("max-cooldown", &config.max_cooldown)?,
+        })
+    }
+}
+
+/// Source的健康状态, 由保存的连续失败次数和最后活动时间计算
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SourceHealth {
+    pub consecutive_failures: u32,
+    pub last_active_at: Option<OffsetDateTime>,
+    /// 在此之前触发器的调度会被跳过
+    pub cooldown_until: Option<OffsetDateTime>,
+}
+
+impl SourceHealth {
+    pub fn new(state: &ProcessorSourceState, policy: &SourceHealthPolicy) -> Self {
+        let cooldown_until = match (state.last_active_at, policy.cooldown(state.retry_times)) {
+            (Some(at), Some(cooldown)) => Some(at + cooldown),
+            _ => None,
+        };
+        SourceHealth {
+            consecutive_failures: state.retry_times,
+            last_active_at: state.last_active_at,
+            cooldown_until,
+        }
+    }
+
+    pub fn is_cooling_down(&self, now: OffsetDateTime) -> bool {
+        self.cooldown_until.is_some_and(|until| until > now)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    #[test]
+    fn cooldown_grows_exponentially() {
+        let policy = SourceHealthPolicy {
+            failure_threshold: 3,
+            cooldown: Duration::from_secs(60),
+            max_cooldown: Duration::from_secs(300),
+        };
+        assert_eq!(policy.cooldown(2), None);
+        assert_eq!(policy.cooldown(3), Some(Duration::from_secs(60)));
+        assert_eq!(policy.cooldown(4), Some(Duration::from_secs(120)));
+        assert_eq!(policy.cooldown(5), Some(Duration::from_secs(240)));
+        assert_eq!(policy.cooldown(6), Some(Duration::from_secs(300)));
+        assert_eq!(policy.cooldown(u32::MAX), Some(Duration::from_secs(300)));
+        let disabled = SourceHealthPolicy {
+            failure_threshold: 0,
+            ..policy
+        };
+        assert_eq!(disabled.cooldown(10), None);
+    }
+
+    #[test]
+    fn health_from_state() {
+        let now = OffsetDateTime::now_utc();
+        let mut state = ProcessorSourceState {
+            id: None,
+            processor_name: "test".to_string(),
+            source_id: "test".to_string(),
+            last_pointer: Value::Null,
+            retry_times: 3,
+            last_active_at: Some(now),
+        };
+        let health = SourceHealth::new(&state, &SourceHealthPolicy::default());
+        assert_eq!(health.cooldown_until, Some(now + Duration::from_secs(300)));
+        assert!(health.is_cooling_down(now));
+
+        state.retry_times = 0;
+        let health = SourceHealth::new(&state, &SourceHealthPolicy::default());
+        assert!(!health.is_cooling_down(now));
+    }
+}
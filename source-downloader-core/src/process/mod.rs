@@ -1,3 +1,5 @@
 pub mod variable;
 pub mod file;
-pub mod rule;
\ No newline at end of file
+pub mod rule;
+pub mod retry;
+pub mod health;
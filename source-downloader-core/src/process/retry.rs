@@ -0,0 +1,150 @@
+use crate::config::{RetryConfig, RetryPolicyConfig};
+use backon::ExponentialBuilder;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// 包含首次执行, 1 表示不重试
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            jitter: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn backoff(&self) -> ExponentialBuilder {
+        let builder = ExponentialBuilder::default()
+            .with_min_delay(self.base_delay)
+            .with_max_delay(self.max_delay)
+            .with_max_times(self.max_attempts.saturating_sub(1) as usize);
+        if self.jitter {
+            builder.with_jitter()
+        } else {
+            builder
+        }
+    }
+
+    fn merge(&self, config: &RetryPolicyConfig) -> Result<RetryPolicy, String> {
+        let parse = |name: &str, value: &Option<String>, default: Duration| match value {
+            None => Ok(default),
+            Some(v) => humantime::parse_duration(v)
+                .map_err(|e| format!("retry {} '{}' is invalid: {}", name, v, e)),
+        };
+        let policy = RetryPolicy {
+            max_attempts: config.max_attempts.unwrap_or(self.max_attempts),
+            base_delay: parse("base-delay", &config.base_delay, self.base_delay)?,
+            max_delay: parse("max-delay", &config.max_delay, self.max_delay)?,
+            jitter: config.jitter.unwrap_or(self.jitter),
+        };
+        if policy.max_attempts == 0 {
+            return Err("retry max-attempts must be greater than 0".to_string());
+        }
+        if policy.base_delay > policy.max_delay {
+            return Err(format!(
+                "retry base-delay {:?} is greater than max-delay {:?}",
+                policy.base_delay, policy.max_delay
+            ));
+        }
+        Ok(policy)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicies {
+    pub fetch: RetryPolicy,
+    pub resolve: RetryPolicy,
+    /// 提交下载和移动文件不一定是幂等的, 默认只执行一次, 需要在阶段中单独配置重试
+    pub download: RetryPolicy,
+    pub movement: RetryPolicy,
+}
+
+impl Default for RetryPolicies {
+    fn default() -> Self {
+        let once = RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        };
+        RetryPolicies {
+            fetch: RetryPolicy::default(),
+            resolve: RetryPolicy::default(),
+            download: once,
+            movement: once,
+        }
+    }
+}
+
+impl TryFrom<&RetryConfig> for RetryPolicies {
+    type Error = String;
+
+    fn try_from(config: &RetryConfig) -> Result<Self, Self::Error> {
+        let base = RetryPolicy::default().merge(&config.policy)?;
+        let once = RetryPolicy {
+            max_attempts: 1,
+            ..base
+        };
+        let stage = |base: RetryPolicy, c: &Option<RetryPolicyConfig>| match c {
+            None => Ok(base),
+            Some(c) => base.merge(c),
+        };
+        Ok(RetryPolicies {
+            fetch: stage(base, &config.fetch)?,
+            resolve: stage(base, &config.resolve)?,
+            download: stage(once, &config.download)?,
+            movement: stage(once, &config.movement)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stage_overrides_base() {
+        let config: RetryConfig = serde_yaml::from_str(
+            r#"
+max-attempts: 6
+max-delay: 1m
+jitter: true
+move:
+  max-attempts: 1
+fetch:
+  base-delay: 5s
+"#,
+        )
+        .unwrap();
+        let policies = RetryPolicies::try_from(&config).unwrap();
+        assert_eq!(policies.fetch.max_attempts, 6);
+        assert_eq!(policies.fetch.base_delay, Duration::from_secs(5));
+        assert_eq!(policies.fetch.max_delay, Duration::from_secs(60));
+        assert!(policies.fetch.jitter);
+        assert_eq!(policies.movement.max_attempts, 1);
+        assert_eq!(policies.movement.base_delay, Duration::from_secs(1));
+        // 下载阶段未单独配置重试次数时只执行一次
+        assert_eq!(policies.download.max_attempts, 1);
+        assert_eq!(policies.download.max_delay, policies.resolve.max_delay);
+        assert_eq!(
+            RetryPolicies::try_from(&RetryConfig::default()).unwrap(),
+            RetryPolicies::default()
+        );
+    }
+
+    #[test]
+    fn invalid_policy() {
+        let config: RetryConfig = serde_yaml::from_str("download:\n  max-attempts: 0").unwrap();
+        assert!(RetryPolicies::try_from(&config).is_err());
+        let config: RetryConfig = serde_yaml::from_str("base-delay: 1 minute later").unwrap();
+        assert!(RetryPolicies::try_from(&config).is_err());
+    }
+}
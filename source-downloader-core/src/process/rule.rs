@@ -1,7 +1,9 @@
 use crate::expression::{source_file_variables, source_item_variables, CompiledExpression};
 use crate::process::file::PathPattern;
 use serde_json::Value;
-use source_downloader_sdk::component::{FileContentFilter, SourceFile, SourceItemFilter, VariableProvider};
+use source_downloader_sdk::component::{
+    FileContentFilter, FileReplacementDecider, SourceFile, SourceItemFilter, VariableProvider,
+};
 use source_downloader_sdk::SourceItem;
 use std::collections::HashSet;
 use std::sync::Arc;
@@ -73,6 +75,7 @@ pub struct FileStrategy {
     pub save_path_pattern: Option<Arc<PathPattern>>,
     pub filename_pattern: Option<Arc<PathPattern>>,
     pub file_content_filters: Option<Vec<Arc<dyn FileContentFilter>>>,
+    pub file_replacement_decider: Option<Arc<dyn FileReplacementDecider>>,
 }
 
 pub struct FileRule {
@@ -3,19 +3,22 @@ use crate::components::expression_file_content_filter::ExpressionFileContentFilt
 use crate::components::expression_item_content_filter::ExpressionItemContentFilter;
 use crate::components::expression_item_filter::ExpressionItemFilter;
 use crate::components::source_item_identity_filter::SourceItemIdentityFilter;
-use crate::config::{ProcessorConfig, ProcessorOptionConfig};
+use crate::config::{ListenerMode, ProcessorConfig, ProcessorOptionConfig};
+use crate::event::ProcessEventBus;
 use crate::expression::CompiledExpressionFactory;
 use crate::expression::cel::FACTORY;
 use crate::process::file::PathPattern;
+use crate::process::health::SourceHealthPolicy;
+use crate::process::retry::RetryPolicies;
 use crate::process::rule::{
     ExpressionAndTagMatcher, FileRule, FileStrategy, ItemRule, ItemStrategy,
 };
 use crate::process::variable::{AnyStrategy, SmartStrategy, VariableAggregation, VoteStrategy};
-use crate::source_processor::{ProcessorOptions, SourceProcessor};
+use crate::source_processor::{ProcessorComponents, ProcessorOptions, SourceProcessor};
 use parking_lot::RwLock;
 use source_downloader_sdk::component::{
     ComponentError, ComponentRootType, FileContentFilter, FileTagger, ItemContentFilter,
-    SourceFileFilter, SourceItemFilter, VariableProvider,
+    ProcessListener, SourceFileFilter, SourceItemFilter, VariableProvider,
 };
 use source_downloader_sdk::storage::ProcessingStorage;
 use std::collections::{HashMap, HashSet};
@@ -23,12 +26,15 @@ use std::ops::Not;
 use std::path::Path;
 use std::string::ToString;
 use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::task::AbortHandle;
 use tracing::{debug, error, info, warn};
 
 pub struct ProcessorManager {
     component_manager: Arc<ComponentManager>,
     processing_storage: Arc<dyn ProcessingStorage>,
     processor_wrappers: RwLock<HashMap<String, Arc<ProcessorWrapper>>>,
+    event_bus: Arc<ProcessEventBus>,
 }
 
 impl ProcessorManager {
@@ -40,9 +46,15 @@ impl ProcessorManager {
             component_manager,
             processing_storage,
             processor_wrappers: RwLock::new(HashMap::new()),
+            event_bus: Arc::new(ProcessEventBus::default()),
         }
     }
 
+    /// 所有处理器共用的事件总线
+    pub fn event_bus(&self) -> &Arc<ProcessEventBus> {
+        &self.event_bus
+    }
+
     pub fn create_processor(&self, config: &ProcessorConfig) {
         if config.enabled.not() {
             info!("Processor[disabled] {}", config.name);
@@ -58,6 +70,7 @@ impl ProcessorManager {
                         name: config.name.to_owned(),
                         processor: None,
                         error_message: Some(err.message),
+                        rename_task: None,
                     }),
                 );
                 return;
@@ -144,20 +157,26 @@ impl ProcessorManager {
             config.name.to_owned(),
             config.source.to_owned(),
             Path::new(&config.save_path).into(),
-            source.to_owned(),
-            item_file_resolver.to_owned(),
-            downloader.to_owned(),
-            file_mover.to_owned(),
-            self.processing_storage.to_owned(),
+            ProcessorComponents {
+                source: source.to_owned(),
+                item_file_resolver: item_file_resolver.to_owned(),
+                downloader: downloader.to_owned(),
+                file_mover: file_mover.to_owned(),
+                processing_storage: self.processing_storage.to_owned(),
+                event_bus: self.event_bus.clone(),
+            },
             config.category.to_owned(),
             config.tags.to_owned(),
             self.create_options(&config, b)?,
         );
         let instance_id = processor.instance_id();
+        let processor = Arc::new(processor);
+        let rename_task = Self::spawn_rename_task(&processor);
         let wrapper = Arc::new(ProcessorWrapper {
             name: config.name.to_owned(),
-            processor: Some(Arc::new(processor)),
+            processor: Some(processor),
             error_message: None,
+            rename_task,
         });
         self.processor_wrappers
             .write()
@@ -166,6 +185,39 @@ impl ProcessorManager {
         Ok(wrapper)
     }
 
+    /// AsyncDownloader的Processor需要定时重命名下载完成的Item
+    fn spawn_rename_task(processor: &Arc<SourceProcessor>) -> Option<AbortHandle> {
+        if !processor.is_async_downloader() {
+            return None;
+        }
+        let Ok(handle) = Handle::try_current() else {
+            warn!(
+                "Processor {} rename task not started, no runtime available",
+                processor.name
+            );
+            return None;
+        };
+        let interval = processor.rename_task_interval();
+        let weak = Arc::downgrade(processor);
+        let task = handle.spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                let Some(processor) = weak.upgrade() else {
+                    break;
+                };
+                if let Err(e) = processor.run_rename().await {
+                    warn!(
+                        "Processor {} rename task failed: {}",
+                        processor.name,
+                        e.message()
+                    );
+                }
+            }
+        });
+        info!("Processor[rename-task-started] {}", processor.name);
+        Some(task.abort_handle())
+    }
+
     fn create_options(
         &self,
         config: &ProcessorConfig,
@@ -286,7 +338,8 @@ impl ProcessorManager {
             );
         }
         // ===
-        let mut process_listeners = vec![];
+        let mut process_listeners: HashMap<ListenerMode, Vec<Arc<dyn ProcessListener>>> =
+            HashMap::new();
         for x in &config.options.process_listeners {
             let component_id = ComponentRootType::ProcessListener.parse_component_id(&x.id);
             let listener = self
@@ -295,7 +348,7 @@ impl ProcessorManager {
                 .require_component()?
                 .as_process_listener()?
                 .clone();
-            process_listeners.push(listener);
+            process_listeners.entry(x.mode).or_default().push(listener);
         }
 
         // ==
@@ -308,6 +361,18 @@ impl ProcessorManager {
             .require_component()?
             .as_file_exists_detector()?;
 
+        let file_replacement_decider = opt
+            .file_replacement_decider
+            .as_ref()
+            .map(|x| {
+                let component_id = ComponentRootType::FileReplacementDecider.parse_component_id(x);
+                self.component_manager
+                    .get_component(&component_id)?
+                    .require_component()?
+                    .as_file_replacement_decider()
+            })
+            .transpose()?;
+
         Ok(ProcessorOptions {
             save_path_pattern: Arc::new(PathPattern::new_cel(
                 config.options.save_path_pattern.to_owned(),
@@ -323,6 +388,7 @@ impl ProcessorManager {
             file_taggers,
             process_listeners,
             file_exists_detector,
+            file_replacement_decider,
             variable_aggregation: VariableAggregation::new(
                 match &opt.variable_conflict_strategy {
                     None => Box::new(SmartStrategy),
@@ -345,7 +411,16 @@ impl ProcessorManager {
             pointer_batch_mode: config.options.pointer_batch_mode,
             item_rules: self.apply_item_grouping(config, opt, identity_filter)?,
             file_rules: self.apply_file_grouping(config, opt)?,
-            download_options: config.options.download_options.clone().into()
+            download_options: config.options.download_options.clone().into(),
+            retry: RetryPolicies::try_from(&config.options.retry)?,
+            source_health: SourceHealthPolicy::try_from(&config.options.source_health)?,
+            item_timeout: config
+                .options
+                .item_timeout
+                .as_deref()
+                .map(humantime::parse_duration)
+                .transpose()
+                .map_err(|e| e.to_string())?,
         })
     }
 
@@ -585,6 +660,17 @@ impl ProcessorManager {
                 } else {
                     None
                 };
+            let file_replacement_decider =
+                if let Some(ref decider_name) = file_opt_cfg.file_replacement_decider {
+                    let cid =
+                        ComponentRootType::FileReplacementDecider.parse_component_id(decider_name);
+                    let wp = self.component_manager.get_component(&cid)?;
+                    let decider = wp.require_component()?.as_file_replacement_decider()?;
+                    wp.get_and_mark_ref(cfg.name.to_owned());
+                    Some(decider)
+                } else {
+                    None
+                };
             // ===
             let expression_matching = file_opt_cfg
                 .expression_matching
@@ -598,6 +684,7 @@ impl ProcessorManager {
                 save_path_pattern: None,
                 filename_pattern: None,
                 file_content_filters,
+                file_replacement_decider,
             };
             result.push(FileRule {
                 matcher: Box::new(matcher),
@@ -612,10 +699,14 @@ pub struct ProcessorWrapper {
     pub name: String,
     pub processor: Option<Arc<SourceProcessor>>,
     pub error_message: Option<String>,
+    rename_task: Option<AbortHandle>,
 }
 
 impl Drop for ProcessorWrapper {
     fn drop(&mut self) {
+        if let Some(task) = &self.rename_task {
+            task.abort();
+        }
         debug!("ProcessorWp[dropped] {}", self.name);
     }
 }
@@ -46,10 +46,15 @@ pub mod test_support {
     pub fn cfg() -> &'static Arc<YamlConfigOperator> {
         _C.get_or_init(|| Arc::new(YamlConfigOperator::new("./tests/resources/config.yaml")))
     }
+    // 每个测试的tokio runtime不同, runtime结束后连接池会重新建立连接,
+    // sqlite::memory:每个连接都是独立的空数据库, 所以使用临时目录中的文件数据库
+    static _DB_DIR: LazyLock<tempfile::TempDir> =
+        LazyLock::new(|| tempfile::tempdir().expect("Failed to create database dir"));
     pub async fn storage() -> &'static Arc<SeaProcessingStorage> {
         _S.get_or_init(|| async {
+            let url = format!("sqlite://{}", _DB_DIR.path().join("test.db").display());
             Arc::new(
-                SeaProcessingStorage::new("sqlite::memory:")
+                SeaProcessingStorage::new(&url)
                     .await
                     .expect("Failed to conn database"),
             )
@@ -65,6 +70,8 @@ pub mod test_support {
                 .unwrap();
             m.register_supplier(Arc::new(VFS_RESOLVER_SUPPLIER))
                 .unwrap();
+            m.register_supplier(Arc::new(RECORDING_LISTENER_SUPPLIER))
+                .unwrap();
             m
         })
     }
@@ -101,6 +108,83 @@ pub mod test_support {
         }
     }
 
+    pub struct RecordingListenerSupplier;
+    const RECORDING_LISTENER_SUPPLIER: RecordingListenerSupplier = RecordingListenerSupplier {};
+
+    impl ComponentSupplier for RecordingListenerSupplier {
+        fn supply_types(&self) -> Vec<ComponentType> {
+            vec![ComponentType::listener("recording".to_owned())]
+        }
+
+        fn apply(
+            &self,
+            props: &Map<String, Value>,
+        ) -> Result<Arc<dyn SdComponent>, ComponentError> {
+            let label = props
+                .get("label")
+                .and_then(|x| x.as_str())
+                .unwrap_or_default()
+                .to_string();
+            Ok(Arc::new(RecordingListener { label }))
+        }
+
+        fn is_support_no_props(&self) -> bool {
+            true
+        }
+
+        fn get_metadata(&self) -> Option<Box<SdComponentMetadata>> {
+            None
+        }
+    }
+
+    /// 以日志记录调用的监听器, title为panic的Item会使监听器panic
+    #[derive(Debug, SdComponent)]
+    #[component(ProcessListener)]
+    pub struct RecordingListener {
+        label: String,
+    }
+
+    impl Display for RecordingListener {
+        fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+            write!(f, "recording:{}", self.label)
+        }
+    }
+
+    impl ProcessListener for RecordingListener {
+        fn on_item_success(&self, _: &dyn ProcessContext, item_content: &ItemContent) {
+            if item_content.source_item.title == "panic" {
+                panic!("Mock listener failed");
+            }
+            tracing::info!(
+                "[listener-item-success] {} {} {:?}",
+                self.label,
+                item_content.source_item.title,
+                item_content.status
+            );
+        }
+
+        fn on_item_error(&self, ctx: &dyn ProcessContext, item: &SourceItem, _: &ProcessingError) {
+            let reason = ctx
+                .get_item_content(item)
+                .and_then(|x| x.failure_reason.map(str::to_string));
+            tracing::info!(
+                "[listener-item-error] {} {} reason={:?}",
+                self.label,
+                item.title,
+                reason
+            );
+        }
+
+        fn on_process_completed(&self, ctx: &dyn ProcessContext) {
+            tracing::info!(
+                "[listener-completed] {} processed={} has_error={}",
+                self.label,
+                ctx.processed_items().len(),
+                ctx.has_error()
+            );
+        }
+    }
+
     /// 硬编码Mock VFS文件解析器
     #[derive(Debug)]
     pub struct HardCodeVfsFileResolver;
@@ -119,7 +203,10 @@ pub mod test_support {
 
     #[async_trait]
     impl ItemFileResolver for HardCodeVfsFileResolver {
-        async fn resolve_files(&self, source_item: &SourceItem) -> Vec<SourceFile> {
+        async fn resolve_files(
+            &self,
+            source_item: &SourceItem,
+        ) -> Result<Vec<SourceFile>, ProcessingError> {
             let path = PathBuf::from(
                 source_item
                     .download_uri
@@ -127,14 +214,25 @@ pub mod test_support {
                     .strip_prefix("file:/")
                     .expect("Failed to parse file URI"),
             );
+            // case for item timeout
+            if source_item.title == "resolve-hang" {
+                tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+            }
             // case for conflict
             if source_item.title == "conflict" {
-                return vec![
+                return Ok(vec![
                     SourceFile::new(path.clone()),
                     SourceFile::new(path.with_file_name("conflict1")),
-                ];
+                ]);
             }
-            vec![SourceFile::new(path)]
+            // case for multiple files
+            if source_item.title == "multiple" {
+                return Ok(vec![
+                    SourceFile::new(path.clone()),
+                    SourceFile::new(path.with_file_name("multiple2")),
+                ]);
+            }
+            Ok(vec![SourceFile::new(path)])
         }
     }
 
@@ -217,21 +315,30 @@ pub mod test_support {
         }
     }
     struct MockSourcePointer {
-        pub value: Value,
+        pub value: parking_lot::RwLock<Value>,
     }
     impl Default for MockSourcePointer {
         fn default() -> Self {
             MockSourcePointer {
-                value: Value::Object(Map::new()),
+                value: parking_lot::RwLock::new(Value::Object(Map::new())),
             }
         }
     }
     impl SourcePointer for MockSourcePointer {
         fn dump(&self) -> Value {
-            self.value.clone()
+            self.value.read().clone()
         }
 
-        fn update(&self, _: &SourceItem, _: &Arc<dyn ItemPointer>) {}
+        // 合并Item配置的item-pointer, 用于断言pointer的位置
+        fn update(&self, _: &SourceItem, item_pointer: &Arc<dyn ItemPointer>) {
+            let Some(pointer) = item_pointer.as_any().downcast_ref::<MockItemPointer>() else {
+                return;
+            };
+            let mut value = self.value.write();
+            if let (Value::Object(value), Value::Object(update)) = (&mut *value, &pointer.value) {
+                value.extend(update.clone());
+            }
+        }
 
         fn into_any(self: Arc<Self>) -> Arc<dyn Any + Send + Sync> {
             self
@@ -260,9 +367,34 @@ pub mod test_support {
         None,
     }
     #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
     struct ComponentMockConfig {
         #[serde(default)]
         fetch: Vec<ComponentFunctionMockConfig>,
+        // file names that move_file fails on
+        #[serde(default)]
+        move_file_errors: Vec<String>,
+        #[serde(default)]
+        async_downloader: Option<AsyncDownloaderMockConfig>,
+        // file names that already exist
+        #[serde(default)]
+        exists: Vec<String>,
+        // act as a batch mover, fails if any file is not Normal
+        #[serde(default)]
+        batch_move: bool,
+        // act as a FileReplacementDecider if present
+        #[serde(default)]
+        should_replace: Option<bool>,
+    }
+    #[derive(Deserialize)]
+    #[serde(rename_all = "kebab-case")]
+    struct AsyncDownloaderMockConfig {
+        // item titles that download finished, others are downloading
+        #[serde(default)]
+        finished: Vec<String>,
+        // item titles that not found in downloader
+        #[serde(default)]
+        lost: Vec<String>,
     }
 
     struct MockComponentSupplier {}
@@ -275,6 +407,7 @@ pub mod test_support {
                 ComponentType::variable_provider(name.to_owned()),
                 ComponentType::downloader(name.to_owned()),
                 ComponentType::file_mover(name.to_owned()),
+                ComponentType::file_replacement_decider(name.to_owned()),
             ]
         }
 
@@ -286,7 +419,9 @@ pub mod test_support {
             let cfg = serde_json::from_value::<ComponentMockConfig>(Value::Object(props.clone()))
                 .expect("Failed to deserialize ComponentMockConfig");
             Self::apply_source_fetch(&mut mock, cfg.fetch)?;
-            Self::apply_file_mover(&mut mock)?;
+            Self::apply_file_mover(&mut mock, cfg.move_file_errors, cfg.exists, cfg.batch_move)?;
+            Self::apply_replacement_decider(&mut mock, cfg.should_replace)?;
+            Self::apply_async_downloader(&mut mock, cfg.async_downloader)?;
 
             // 配置 default_pointer 方法
             mock.expect_default_pointer()
@@ -294,8 +429,11 @@ pub mod test_support {
 
             // 配置 parse_raw_pointer 方法
             if let Some(_) = props.get("parse_raw_pointer") {
-                mock.expect_parse_raw_pointer()
-                    .returning(|value| Arc::new(MockSourcePointer { value }));
+                mock.expect_parse_raw_pointer().returning(|value| {
+                    Arc::new(MockSourcePointer {
+                        value: parking_lot::RwLock::new(value),
+                    })
+                });
             } else {
                 mock.expect_parse_raw_pointer()
                     .returning(|_| Arc::new(MockSourcePointer::default()));
@@ -323,9 +461,72 @@ pub mod test_support {
             Ok(result)
         }
 
-        fn apply_file_mover(mock: &mut MockComponent) -> Result<(), ComponentError> {
-            mock.expect_exists()
-                .returning(|f| f.iter().map(|x| false).collect_vec());
+        fn apply_file_mover(
+            mock: &mut MockComponent,
+            move_file_errors: Vec<String>,
+            exists: Vec<String>,
+            batch_move: bool,
+        ) -> Result<(), ComponentError> {
+            mock.expect_exists().returning(move |f| {
+                f.iter()
+                    .map(|path| exists.iter().any(|x| path.ends_with(x)))
+                    .collect_vec()
+            });
+            mock.expect_path_metadata()
+                .returning(|path| SourceFile::new(PathBuf::from(path)));
+            mock.expect_replace().returning(|_| Ok(()));
+            mock.expect_create_directories().returning(|_| Ok(()));
+            mock.expect_is_supported_batch_move()
+                .returning(move || batch_move);
+            mock.expect_batch_move().returning(|item| {
+                if item
+                    .file_contents
+                    .iter()
+                    .all(|f| f.status == FileContentStatus::Normal)
+                {
+                    Ok(())
+                } else {
+                    Err(ProcessingError::non_retryable("Mock batch move failed"))
+                }
+            });
+            mock.expect_move_file().returning(move |f, _| {
+                if move_file_errors.iter().any(|x| f.path.ends_with(x)) {
+                    Err(ProcessingError::non_retryable("Mock move failed"))
+                } else {
+                    Ok(())
+                }
+            });
+            Ok(())
+        }
+
+        fn apply_replacement_decider(
+            mock: &mut MockComponent,
+            should_replace: Option<bool>,
+        ) -> Result<(), ComponentError> {
+            mock.expect_replacement_decider()
+                .return_const(should_replace.is_some());
+            if let Some(replace) = should_replace {
+                mock.expect_should_replace().return_const(replace);
+            }
+            Ok(())
+        }
+
+        fn apply_async_downloader(
+            mock: &mut MockComponent,
+            cfg: Option<AsyncDownloaderMockConfig>,
+        ) -> Result<(), ComponentError> {
+            mock.expect_async_downloader().return_const(cfg.is_some());
+            if let Some(cfg) = cfg {
+                mock.expect_is_finished().returning(move |item| {
+                    if cfg.finished.contains(&item.title) {
+                        Some(true)
+                    } else if cfg.lost.contains(&item.title) {
+                        None
+                    } else {
+                        Some(false)
+                    }
+                });
+            }
             Ok(())
         }
 
@@ -383,9 +584,29 @@ pub mod test_support {
         fn as_downloader(self: Arc<Self>) -> Result<Arc<dyn Downloader>, ComponentError> {
             Ok(self)
         }
+        fn as_async_downloader(
+            self: Arc<Self>,
+        ) -> Result<Arc<dyn AsyncDownloader>, ComponentError> {
+            if self.async_downloader() {
+                Ok(self)
+            } else {
+                Err(ComponentError::from("Not a async downloader component"))
+            }
+        }
         fn as_file_mover(self: Arc<Self>) -> Result<Arc<dyn FileMover>, ComponentError> {
             Ok(self)
         }
+        fn as_file_replacement_decider(
+            self: Arc<Self>,
+        ) -> Result<Arc<dyn FileReplacementDecider>, ComponentError> {
+            if self.replacement_decider() {
+                Ok(self)
+            } else {
+                Err(ComponentError::from(
+                    "Not a file replacement decider component",
+                ))
+            }
+        }
         fn as_variable_provider(
             self: Arc<Self>,
         ) -> Result<Arc<dyn VariableProvider>, ComponentError> {
@@ -400,7 +621,10 @@ pub mod test_support {
 
     mock! {
         #[derive(Debug)]
-        pub Component {}
+        pub Component {
+            fn async_downloader(&self) -> bool;
+            fn replacement_decider(&self) -> bool;
+        }
         #[async_trait]
         impl Source for Component {
             async fn fetch(
@@ -413,7 +637,7 @@ pub mod test_support {
         }
         #[async_trait]
         impl ItemFileResolver for Component {
-            async fn resolve_files(&self, item: &SourceItem) -> Vec<SourceFile>;
+            async fn resolve_files(&self, item: &SourceItem) -> Result<Vec<SourceFile>, ProcessingError>;
         }
         #[async_trait]
         impl VariableProvider for Component {
@@ -430,7 +654,7 @@ pub mod test_support {
         }
         #[async_trait]
         impl FileMover for Component {
-            fn move_file(&self, source_file: &SourceFile,download_path: &str) -> Result<(), ProcessingError>;
+            fn move_file(&self, source_file: &SourceFile, target_path: &str) -> Result<(), ProcessingError>;
             fn exists<'a>(&self, path: &Vec<&'a PathBuf>) -> Vec<bool>;
             fn create_directories(&self, path: &str) -> Result<(), ProcessingError>;
             fn replace<'a>(&self, item_content: &ItemContent<'a>) -> Result<(), ProcessingError>;
@@ -439,12 +663,32 @@ pub mod test_support {
             fn is_supported_batch_move(&self) -> bool;
             fn batch_move<'a>(&self, item_content: &ItemContent<'a>) -> Result<(), ProcessingError>;
         }
+        #[async_trait]
+        impl AsyncDownloader for Component {
+            async fn is_finished(&self, item: &SourceItem) -> Option<bool>;
+        }
+        impl FileReplacementDecider for Component {
+            fn should_replace<'a, 'b, 'c>(
+                &self,
+                current: &ItemContent<'a>,
+                before: Option<&'c InProcessingItem<'b>>,
+                existing_file: &SourceFile,
+            ) -> bool;
+        }
     }
 
     #[async_trait]
     impl Downloader for MockComponent {
         async fn submit(&self, task: &DownloadTask) -> Result<(), ProcessingError> {
-            Ok(())
+            match task.source_item.title.as_str() {
+                "submit-error" => Err(ProcessingError::non_retryable("Mock submit failed")),
+                "submit-skip" => Err(ProcessingError::skip("Mock submit skipped")),
+                "submit-slow" => {
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                    Ok(())
+                }
+                _ => Ok(()),
+            }
         }
 
         fn default_download_path(&self) -> &str {
@@ -1,32 +1,47 @@
+use crate::components::simple_file_exists_detector::SimpleFileExistsDetector;
+use crate::components::source_item_identity_filter::SourceItemIdentityFilter;
+use crate::config::ListenerMode;
+use crate::event::{ProcessEvent, ProcessEventBus, ProcessEventKind};
 use crate::process::file::{PathPattern, RawFileContent, Renamer};
+use crate::process::health::SourceHealthPolicy;
+use crate::process::retry::{RetryPolicies, RetryPolicy};
 use crate::process::rule::{FileRule, ItemRule, ItemStrategy};
 use crate::process::variable::VariableAggregation;
 use async_trait::async_trait;
 use backon::Retryable;
-use backon::{BackoffBuilder, ExponentialBuilder};
+use futures_util::{StreamExt, future, stream};
 use humantime::format_duration;
 use itertools::Itertools;
+use ouroboros::self_referencing;
 use parking_lot::RwLock;
+use serde::Serialize;
 use source_downloader_sdk::SourceItem;
 use source_downloader_sdk::component::FileContentStatus::{
-    FileConflict, Normal, TargetExists, Undetected, VariableError,
+    self, FileConflict, Normal, ReadyReplace, Replace, Replaced, TargetExists, Undetected,
+    VariableError,
 };
 use source_downloader_sdk::component::{
-    DownloadOptions, DownloadTask, Downloader, FileContentFilter, FileExistsDetector,
-    InProcessingItem, ItemContent, ItemContentFilter, ProcessListener, SourceFileFilter,
-    SourceFileRef, SourceItemFilter,
+    AsyncDownloader, DownloadOptions, DownloadTask, Downloader, EMPTY_POINTER, FileContentFilter,
+    FileExistsDetector, FileReplacementDecider, InProcessingItem, ItemContent, ItemContentFilter,
+    ProcessContext, ProcessListener, ProcessorInfo, SourceFileFilter, SourceFileRef,
+    SourceItemFilter,
 };
 use source_downloader_sdk::component::{FileContent, Source};
 use source_downloader_sdk::component::{FileMover, ProcessingError};
 use source_downloader_sdk::component::{FileTagger, ProcessTask, SourceFile};
-use source_downloader_sdk::component::{ItemFileResolver, ItemPointer, SourcePointer};
+use source_downloader_sdk::component::{ItemFileResolver, ItemPointer, PointedItem, SourcePointer};
 use source_downloader_sdk::component::{PatternVariables, VariableProvider};
+use source_downloader_sdk::serde_json::{Map, Value};
 use source_downloader_sdk::storage::{
-    ItemContentLite, ProcessingContent, ProcessingStatus, ProcessingStorage, ProcessorSourceState,
+    ItemContentLite, ProcessingContent, ProcessingContentQuery, ProcessingStatus,
+    ProcessingStorage, ProcessingTargetPath, ProcessorRun, ProcessorSourceState, RunOutcome,
 };
 use source_downloader_sdk::time::OffsetDateTime;
+use std::any::Any;
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::io::Cursor;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, Ordering};
@@ -34,8 +49,11 @@ use std::time::{Duration, Instant};
 use tokio::fs::OpenOptions;
 use tokio::io::AsyncWriteExt;
 use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+pub use crate::process::health::SourceHealth;
+
 static INSTANCE_ID_GENERATOR: AtomicI64 = AtomicI64::new(0);
 static PROCESS_ID_GENERATOR: AtomicI64 = AtomicI64::new(i64::MIN);
 // static EMPTY_FILES: Vec<FileContent> = vec![];
@@ -66,8 +84,44 @@ pub struct SourceProcessor {
     options: ProcessorOptions,
     instance_id: i64,
     processing: AtomicBool,
+    renaming: AtomicBool,
     renamer: Renamer,
     download_path: Box<Path>,
+    // 当前运行的取消信号, 运行结束后重置
+    cancel_token: parking_lot::Mutex<CancellationToken>,
+    cancel_submitted: AtomicBool,
+    created_at: OffsetDateTime,
+    run_state: parking_lot::RwLock<RunState>,
+    // 首次使用时从存储中加载
+    source_health: parking_lot::RwLock<Option<SourceHealth>>,
+    event_bus: Arc<ProcessEventBus>,
+}
+
+#[derive(Default)]
+struct RunState {
+    last_start_process_time: Option<OffsetDateTime>,
+    last_end_process_time: Option<OffsetDateTime>,
+    last_process_failed_message: Option<String>,
+}
+
+/// 处理器的运行时状态
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeSnapshot {
+    pub created_at: OffsetDateTime,
+    pub last_start_process_time: Option<OffsetDateTime>,
+    pub last_end_process_time: Option<OffsetDateTime>,
+    pub last_process_failed_message: Option<String>,
+    pub processing: bool,
+}
+
+/// 处理器依赖的组件和服务
+pub struct ProcessorComponents {
+    pub source: Arc<dyn Source>,
+    pub item_file_resolver: Arc<dyn ItemFileResolver>,
+    pub downloader: Arc<dyn Downloader>,
+    pub file_mover: Arc<dyn FileMover>,
+    pub processing_storage: Arc<dyn ProcessingStorage>,
+    pub event_bus: Arc<ProcessEventBus>,
 }
 
 pub struct ProcessorOptions {
@@ -105,16 +159,30 @@ pub struct ProcessorOptions {
     pub item_rules: Vec<ItemRule>,
     // ok
     pub file_rules: Vec<FileRule>,
-    pub process_listeners: Vec<Arc<dyn ProcessListener>>,
+    pub process_listeners: HashMap<ListenerMode, Vec<Arc<dyn ProcessListener>>>,
     pub file_exists_detector: Arc<dyn FileExistsDetector>,
+    pub file_replacement_decider: Option<Arc<dyn FileReplacementDecider>>,
     // ok
     pub download_options: DownloadOptions,
+    pub retry: RetryPolicies,
+    pub source_health: SourceHealthPolicy,
+    pub item_timeout: Option<Duration>,
 }
 
 #[async_trait]
 impl ProcessTask for SourceProcessor {
     async fn run(&self) -> Result<(), String> {
-        let p = NormalProcess {};
+        let health = self.source_health().await.map_err(|x| x.to_string())?;
+        if health.is_cooling_down(OffsetDateTime::now_utc()) {
+            info!(
+                "[source-cooldown] {} skipped, consecutive failures:{} cooldown until:{:?}",
+                self.name, health.consecutive_failures, health.cooldown_until
+            );
+            return Ok(());
+        }
+        let p = NormalProcess {
+            trigger_source: "trigger",
+        };
         p.execute(self).await.map_err(|x| x.to_string())
     }
 
@@ -123,7 +191,7 @@ impl ProcessTask for SourceProcessor {
     }
 
     fn group(&self) -> Option<String> {
-        self.source.group()
+        self.options.task_group.clone()
     }
 }
 
@@ -136,11 +204,21 @@ struct ProcessRuntime {
     process_submitted_items: RwLock<HashSet<String>>,
     processed_count: AtomicU32,
     filter_count: AtomicU32,
+    fetched_count: u32,
+    started_at: OffsetDateTime,
     process_start_at: Option<Instant>,
     process_end_at: Option<Instant>,
     fetch_start_at: Option<Instant>,
     fetch_end_at: Option<Instant>,
     cancel_items: Vec<SourceItem>,
+    listener_context: parking_lot::Mutex<ListenerContext>,
+    cancel_token: CancellationToken,
+    // 本次运行提交到下载器还未重命名的Item
+    submitted_items: parking_lot::Mutex<Vec<(SourceItem, Vec<SourceFile>)>>,
+    // 试运行等不保存状态的处理不发布事件
+    publish_events: bool,
+    // 出现不可跳过的异常后不再更新pointer
+    pointer_frozen: AtomicBool,
 }
 
 enum ItemAction {
@@ -152,11 +230,20 @@ enum ItemAction {
         files: Vec<FileContent>,
     },
     // 处理失败
-    #[allow(dead_code)]
     Error(ProcessingError),
+    // 运行被取消时中断的Item, 不保存任何信息, 下次触发时重新处理
+    Cancelled(ProcessingError),
 }
 
 impl ProcessRuntime {
+    fn publish(&self, p: &SourceProcessor, kind: ProcessEventKind) {
+        if self.publish_events {
+            p.publish_event(Some(&self.trace_id), kind);
+        }
+    }
+    fn is_cancelled(&self) -> bool {
+        self.cancel_token.is_cancelled()
+    }
     fn filter_inc(&self) {
         self.filter_count.fetch_add(1, Ordering::Relaxed);
     }
@@ -210,20 +297,37 @@ impl Drop for ProcessingGuard<'_> {
     }
 }
 
+/// 运行结束时重置取消信号, 与[SourceProcessor::cancel]持有同一把锁避免取消到下一次运行
+struct RunGuard<'a> {
+    p: &'a SourceProcessor,
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        let mut token = self.p.cancel_token.lock();
+        *token = CancellationToken::new();
+        self.p.processing.store(false, Ordering::Release);
+    }
+}
+
 impl SourceProcessor {
     pub fn new(
         name: String,
         source_id: String,
         save_path: Box<Path>,
-        source: Arc<dyn Source>,
-        item_file_resolver: Arc<dyn ItemFileResolver>,
-        downloader: Arc<dyn Downloader>,
-        file_mover: Arc<dyn FileMover>,
-        processing_storage: Arc<dyn ProcessingStorage>,
+        components: ProcessorComponents,
         category: Option<String>,
         tags: HashSet<String>,
         options: ProcessorOptions,
     ) -> Self {
+        let ProcessorComponents {
+            source,
+            item_file_resolver,
+            downloader,
+            file_mover,
+            processing_storage,
+            event_bus,
+        } = components;
         let download_path = Path::new(downloader.default_download_path()).into();
         Self {
             name,
@@ -239,20 +343,320 @@ impl SourceProcessor {
             options,
             instance_id: INSTANCE_ID_GENERATOR.fetch_add(1, Ordering::Relaxed),
             processing: AtomicBool::new(false),
+            renaming: AtomicBool::new(false),
             renamer: Renamer::default(),
             download_path,
+            cancel_token: parking_lot::Mutex::new(CancellationToken::new()),
+            cancel_submitted: AtomicBool::new(false),
+            created_at: OffsetDateTime::now_utc(),
+            run_state: parking_lot::RwLock::new(RunState::default()),
+            source_health: parking_lot::RwLock::new(None),
+            event_bus,
+        }
+    }
+
+    pub fn runtime_snapshot(&self) -> RuntimeSnapshot {
+        let state = self.run_state.read();
+        RuntimeSnapshot {
+            created_at: self.created_at,
+            last_start_process_time: state.last_start_process_time,
+            last_end_process_time: state.last_end_process_time,
+            last_process_failed_message: state.last_process_failed_message.clone(),
+            processing: self.processing.load(Ordering::Acquire),
+        }
+    }
+
+    /// 取消正在进行的运行, 已处理的Item的pointer会被保存, 返回false表示当前没有在运行
+    pub fn cancel(&self, cancel_submitted: bool) -> bool {
+        let token = self.cancel_token.lock();
+        if !self.processing.load(Ordering::Acquire) {
+            return false;
+        }
+        self.cancel_submitted
+            .store(cancel_submitted, Ordering::Release);
+        token.cancel();
+        info!("[run-cancel] {}({})", self.name, self.instance_id);
+        true
+    }
+
+    pub async fn source_health(&self) -> Result<SourceHealth, ProcessingError> {
+        if let Some(health) = self.source_health.read().clone() {
+            return Ok(health);
         }
+        let health = match self
+            .processing_storage
+            .find_processor_source_state(&self.name, &self.source_id)
+            .await
+            .map_err(|x| ProcessingError::non_retryable(x.message))?
+        {
+            Some(state) => SourceHealth::new(&state, &self.options.source_health),
+            None => SourceHealth::default(),
+        };
+        *self.source_health.write() = Some(health.clone());
+        Ok(health)
+    }
+
+    fn publish_event(&self, trace_id: Option<&str>, kind: ProcessEventKind) {
+        self.event_bus.publish(ProcessEvent {
+            processor_name: self.name.clone(),
+            trace_id: trace_id.map(str::to_owned),
+            timestamp: OffsetDateTime::now_utc(),
+            kind,
+        });
+    }
+
+    fn update_source_health(&self, state: &ProcessorSourceState) {
+        *self.source_health.write() = Some(SourceHealth::new(state, &self.options.source_health));
+    }
+
+    fn cancel_token(&self) -> CancellationToken {
+        self.cancel_token.lock().clone()
     }
 
     pub fn instance_id(&self) -> i64 {
         self.instance_id
     }
 
-    pub async fn dry_run(&self) {
-        DryRunProcess {};
+    /// 试运行处理流程, 不保存任何状态也不会下载和移动文件
+    pub async fn dry_run(
+        &self,
+        options: DryRunOptions,
+    ) -> Result<Vec<DryRunResult>, ProcessingError> {
+        let (sender, mut receiver) = tokio::sync::mpsc::channel(16);
+        let collect = async move {
+            let mut results = vec![];
+            while let Some(result) = receiver.recv().await {
+                results.push(result);
+            }
+            results
+        };
+        let (result, results) = tokio::join!(self.dry_run_stream(options, sender), collect);
+        result.map(|_| results)
+    }
+
+    /// 每处理完一个Item就发送结果, 接收端关闭后停止处理
+    ///
+    /// 试运行不占用运行状态, 可以和正常的运行同时进行, 也不会被[SourceProcessor::cancel]取消
+    pub async fn dry_run_stream(
+        &self,
+        options: DryRunOptions,
+        sender: tokio::sync::mpsc::Sender<DryRunResult>,
+    ) -> Result<(), ProcessingError> {
+        let span_exec = tracing::info_span!("", processor = self.name);
+        let _span_exec_entered = span_exec.enter();
+        info!("[dry-run-start] {}({})", self.name, self.instance_id);
+        let process = DryRunProcess {
+            options,
+            sender,
+            pending: parking_lot::Mutex::new(HashMap::new()),
+        };
+        let mut p_rt = process.init_process_context(self, Instant::now()).await?;
+        process.run_process(self, &mut p_rt).await
+    }
+
+    /// 手动触发运行
+    pub async fn run_manually(&self) -> Result<(), ProcessingError> {
+        NormalProcess {
+            trigger_source: "manual",
+        }
+        .execute(self)
+        .await
+    }
+
+    /// 处理指定的Item而不是从Source获取, 不会更新Source的pointer
+    pub async fn run_items(&self, items: Vec<SourceItem>) -> Result<(), ProcessingError> {
+        FixedItemProcess { items }.execute(self).await
+    }
+
+    /// 使用当前的配置重新处理已保存的内容, 不会重新下载, 目标路径变化的文件会被重新移动
+    pub async fn reprocess(&self, content_id: i64) -> Result<(), ProcessingError> {
+        if self.processing.swap(true, Ordering::AcqRel) {
+            info!(
+                "[reprocess-reject] {}({}) Already processing",
+                self.name, self.instance_id
+            );
+            return Err(ProcessingError::non_retryable("Already processing"));
+        }
+        let _run_guard = RunGuard { p: self };
+        let content = self
+            .processing_storage
+            .find_content_by_id(content_id)
+            .await
+            .map_err(|x| ProcessingError::non_retryable(x.message))?
+            .ok_or_else(|| {
+                ProcessingError::non_retryable(format!("Content {} not found", content_id))
+            })?;
+        if content.processor_name != self.name {
+            return Err(ProcessingError::non_retryable(format!(
+                "Content {} not belongs to processor {}",
+                content_id, self.name
+            )));
+        }
+        let files = self
+            .processing_storage
+            .find_file_contents(content_id)
+            .await
+            .map_err(|x| ProcessingError::non_retryable(x.message))?
+            .map(|bytes| decode_files_from_compressed(&bytes))
+            .transpose()?
+            .unwrap_or_default();
+        let process = Reprocess { content, files };
+        let rt = process.init_process_context(self, Instant::now()).await?;
+        let source_item = &process.content.item_content.source_item;
+        info!("[reprocess-start] {} {}", self.name, source_item);
+        process.process_item(source_item, &rt, self).await?;
+        Ok(())
+    }
+
+    pub fn is_async_downloader(&self) -> bool {
+        self.downloader.clone().as_async_downloader().is_ok()
+    }
+
+    pub fn rename_task_interval(&self) -> Duration {
+        self.options.rename_task_interval
+    }
+
+    /// 重命名AsyncDownloader下载完成的WaitingToRename的Item
+    pub async fn run_rename(&self) -> Result<(), ProcessingError> {
+        let Ok(downloader) = self.downloader.clone().as_async_downloader() else {
+            return Ok(());
+        };
+        if self.renaming.swap(true, Ordering::AcqRel) {
+            info!("[rename-reject] {} Already renaming", self.name);
+            return Err(ProcessingError::non_retryable("Already renaming"));
+        }
+        let _renaming_guard = ProcessingGuard::new(&self.renaming);
+        let contents = self
+            .processing_storage
+            .query_processing_content(&ProcessingContentQuery {
+                processor_name: Some(vec![self.name.clone()]),
+                status: Some(vec![ProcessingStatus::WaitingToRename]),
+                ..Default::default()
+            })
+            .await
+            .map_err(|x| ProcessingError::non_retryable(x.message))?;
+        if contents.is_empty() {
+            return Ok(());
+        }
+
+        let total = contents.len();
+        let mut renamed = 0;
+        let mut listener_ctx = ListenerContext::new(self);
+        for content in contents {
+            let item = content.item_content.source_item.to_string();
+            match self
+                .rename_content(downloader.as_ref(), content, &mut listener_ctx)
+                .await
+            {
+                Ok(true) => renamed += 1,
+                Ok(false) => {}
+                Err(e) => error!("[rename-error] {} cause={}", item, e.message()),
+            }
+        }
+        // 只有完成重命名(成功或失败)的Item才通知监听器
+        if !listener_ctx.processed_items.is_empty() {
+            notify_process_completed(self, &listener_ctx);
+        }
+        info!("[rename-done] {} renamed {}/{}", self.name, renamed, total);
+        Ok(())
     }
 
-    pub async fn reprocess(&self) {}
+    async fn rename_content(
+        &self,
+        downloader: &dyn AsyncDownloader,
+        mut content: ProcessingContent,
+        listener_ctx: &mut ListenerContext,
+    ) -> Result<bool, ProcessingError> {
+        let Some(content_id) = content.id else {
+            return Ok(false);
+        };
+        match downloader
+            .is_finished(&content.item_content.source_item)
+            .await
+        {
+            Some(true) => {}
+            Some(false) => return Ok(false),
+            None => {
+                // 下载器中找不到对应的任务, 大概率是被手动删除了
+                content.rename_times += 1;
+                if content.rename_times >= self.options.rename_times_threshold {
+                    warn!(
+                        "[rename-download-failed] {} rename times:{}",
+                        content.item_content.source_item, content.rename_times
+                    );
+                    content.status = ProcessingStatus::DownloadFailed;
+                    content.failure_reason =
+                        Some("Download task not found in downloader".to_string());
+                }
+                content.updated_at = Some(OffsetDateTime::now_utc());
+                self.processing_storage
+                    .save_processing_content(&content)
+                    .await
+                    .map_err(|x| ProcessingError::non_retryable(x.message))?;
+                return Ok(false);
+            }
+        }
+
+        let mut files = self
+            .processing_storage
+            .find_file_contents(content_id)
+            .await
+            .map_err(|x| ProcessingError::non_retryable(x.message))?
+            .map(|bytes| decode_files_from_compressed(&bytes))
+            .transpose()?
+            .unwrap_or_default();
+        let process = NormalProcess {
+            trigger_source: "rename",
+        };
+        let source_item = &content.item_content.source_item;
+        let item_variables = &content.item_content.item_variables;
+        let result = match process
+            .do_movement(self, source_item, item_variables, &mut files)
+            .await
+        {
+            Ok(_) => {
+                process
+                    .do_replacement(self, source_item, item_variables, &mut files)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        content.rename_times += 1;
+        content.updated_at = Some(OffsetDateTime::now_utc());
+        let renamed = match result {
+            Ok(_) => {
+                content.status = ProcessingStatus::Renamed;
+                content.failure_reason = None;
+                self.publish_event(
+                    None,
+                    ProcessEventKind::ItemRenamed {
+                        item: source_item.into(),
+                    },
+                );
+                true
+            }
+            Err(e) => {
+                content.status = ProcessingStatus::Failure;
+                content.failure_reason = Some(e.message().to_string());
+                self.publish_event(
+                    None,
+                    ProcessEventKind::ItemFailed {
+                        item: source_item.into(),
+                        message: e.message().to_string(),
+                    },
+                );
+                false
+            }
+        };
+        process
+            .on_item_process_complete(self, &content, &files)
+            .await?;
+        let source_item = content.item_content.source_item.clone();
+        listener_ctx.record_item(content, files);
+        notify_item_processed(self, listener_ctx, &source_item);
+        Ok(renamed)
+    }
 
     async fn save_source_state(&self, state: &ProcessorSourceState) -> Result<(), String> {
         self.processing_storage
@@ -262,28 +666,55 @@ impl SourceProcessor {
             .map(|_| ())
     }
 
-    pub async fn apply_retry<T, Fut, F>(mut f: F, stage: &str) -> Result<T, ProcessingError>
+    /// 取消后不再重试, 等待中的重试和执行中的调用都会立即返回
+    pub async fn apply_retry<T, Fut, F>(
+        mut f: F,
+        stage: &str,
+        policy: &RetryPolicy,
+        cancel_token: &CancellationToken,
+    ) -> Result<T, ProcessingError>
     where
         F: FnMut() -> Fut,
         Fut: Future<Output = Result<T, ProcessingError>>,
     {
-        (|| f())
-            .retry(
-                ExponentialBuilder::default()
-                    .with_max_times(3)
-                    .with_max_delay(Duration::from_secs(10))
-                    .build(),
-            )
-            .when(|e| matches!(e, ProcessingError::Retryable { .. }))
-            .notify(|err, dur| {
-                warn!(
-                    "Retrying {} delay {} cause={} ",
-                    stage,
-                    format_duration(dur),
-                    err.message()
-                );
-            })
-            .await
+        let sleep_token = cancel_token.clone();
+        let result = (|| {
+            let attempt = f();
+            async move {
+                tokio::select! {
+                    _ = cancel_token.cancelled() => Err(
+                        ProcessingError::non_retryable(format!("{} cancelled", stage)),
+                    ),
+                    result = attempt => result,
+                }
+            }
+        })
+        .retry(policy.backoff())
+        .sleep(move |dur| {
+            let cancel_token = sleep_token.clone();
+            async move {
+                tokio::select! {
+                    _ = cancel_token.cancelled() => {}
+                    _ = tokio::time::sleep(dur) => {}
+                }
+            }
+        })
+        .when(|e| matches!(e, ProcessingError::Retryable { .. }) && !cancel_token.is_cancelled())
+        .notify(|err, dur| {
+            warn!(
+                "Retrying {} delay {} cause={} ",
+                stage,
+                format_duration(dur),
+                err.message()
+            );
+        })
+        .await;
+        match result {
+            Err(ProcessingError::Retryable { .. }) if cancel_token.is_cancelled() => Err(
+                ProcessingError::non_retryable(format!("{} cancelled", stage)),
+            ),
+            _ => result,
+        }
     }
 }
 
@@ -295,7 +726,45 @@ impl Drop for SourceProcessor {
 
 #[allow(dead_code)]
 trait Process {
-    fn select_item_filter<'a>(&self, p: &'a SourceProcessor) -> &'a Vec<Arc<dyn SourceItemFilter>>;
+    fn select_item_filter<'a>(
+        &self,
+        p: &'a SourceProcessor,
+        item_strategy: Option<&'a ItemStrategy>,
+    ) -> Vec<&'a Arc<dyn SourceItemFilter>> {
+        item_strategy
+            .and_then(|x| x.item_filters.as_ref())
+            .unwrap_or(&p.options.item_filters)
+            .iter()
+            .collect()
+    }
+
+    /// 是否在下载后立即移动文件, 否则等待重命名任务处理
+    fn rename_immediately(&self, p: &SourceProcessor) -> bool {
+        !p.is_async_downloader()
+    }
+
+    /// 每个Item处理前检查, 返回true时结束本次处理
+    fn is_interrupted(&self) -> bool {
+        false
+    }
+
+    /// 本次处理使用的取消信号
+    fn cancel_token(&self, p: &SourceProcessor) -> CancellationToken {
+        p.cancel_token()
+    }
+
+    /// 运行记录的触发来源, None表示不保存运行记录
+    fn trigger_source(&self) -> Option<&str> {
+        None
+    }
+
+    async fn on_fetch_complete(
+        &self,
+        _p: &SourceProcessor,
+        _ctx: &mut ProcessRuntime,
+        _result: &Result<Vec<PointedItem>, ProcessingError>,
+    ) {
+    }
 
     async fn on_process_complete(
         &self,
@@ -311,16 +780,19 @@ trait Process {
         files: &Vec<FileContent>,
     ) -> Result<(), ProcessingError>;
 
-    async fn on_item_error<'a>(
+    #[allow(unused)]
+    async fn on_item_error(
         &self,
-        _p: &SourceProcessor,
-        _ctx: &ProcessRuntime,
-        _in_processing: &InProcessingItem<'a>,
-        _err: &ProcessingError,
+        p: &SourceProcessor,
+        ctx: &ProcessRuntime,
+        source_item: &SourceItem,
+        err: &ProcessingError,
     ) {
-        // TODO invoke hooks
     }
 
+    #[allow(unused)]
+    async fn on_item_skip(&self, p: &SourceProcessor, source_item: &SourceItem, reason: &str) {}
+
     #[allow(unused)]
     async fn on_item_success(
         &self,
@@ -344,35 +816,135 @@ trait Process {
             );
             return Err(ProcessingError::non_retryable("Already processing"));
         }
-        let _processing_guard = ProcessingGuard::new(&p.processing);
-        let mut p_rt = self.init_process_context(p, start_time).await?;
+        let _run_guard = RunGuard { p };
+        p.cancel_submitted.store(false, Ordering::Release);
+        p.run_state.write().last_start_process_time = Some(OffsetDateTime::now_utc());
+        let result = match self.init_process_context(p, start_time).await {
+            Ok(mut p_rt) => {
+                p_rt.publish_events = self.trigger_source().is_some();
+                if let Some(trigger_source) = self.trigger_source() {
+                    p_rt.publish(
+                        p,
+                        ProcessEventKind::RunStarted {
+                            trigger_source: trigger_source.to_string(),
+                        },
+                    );
+                }
+                let result = self.run_process(p, &mut p_rt).await;
+                if let Some(trigger_source) = self.trigger_source() {
+                    save_processor_run(p, &p_rt, trigger_source, &result).await;
+                }
+                p_rt.publish(
+                    p,
+                    ProcessEventKind::RunFinished {
+                        fetched_count: p_rt.fetched_count,
+                        processed_count: p_rt.processed_count.load(Ordering::Acquire),
+                        filtered_count: p_rt.filter_count.load(Ordering::Acquire),
+                        error: result.as_ref().err().map(|e| e.message().to_string()),
+                    },
+                );
+                result
+            }
+            Err(e) => Err(e),
+        };
+        let mut state = p.run_state.write();
+        state.last_end_process_time = Some(OffsetDateTime::now_utc());
+        state.last_process_failed_message = result.as_ref().err().map(|e| e.message().to_string());
+        result
+    }
+
+    async fn run_process(
+        &self,
+        p: &SourceProcessor,
+        p_rt: &mut ProcessRuntime,
+    ) -> Result<(), ProcessingError> {
         let source_pointer = p_rt.source_pointer.clone();
         debug!("Fetch with pointer: {}", p_rt.source_pointer.dump());
         p_rt.fetch_start_at = Some(Instant::now());
-        let items = SourceProcessor::apply_retry(
-            || async {
-                p.source
-                    .fetch(source_pointer.clone(), p.options.fetch_limit)
-                    .await
-            },
-            "fetch-source-items",
-        )
-        .await?;
+        let fetch_result = self.fetch_items(p, &source_pointer).await;
+        self.on_fetch_complete(p, p_rt, &fetch_result).await;
+        let items = fetch_result?;
         p_rt.fetch_end_at = Some(Instant::now());
-
-        for item in items {
+        p_rt.fetched_count = items.len() as u32;
+
+        let parallelism = p.options.parallelism.max(1) as usize;
+        // 出现不可跳过的异常后不再取新的Item, 已经在处理中的Item仍然处理完成
+        let stopped = AtomicBool::new(false);
+        // buffered保持Item的顺序, pointer按照Source的顺序更新
+        let mut item_results = stream::iter(items)
+            .take_while(|_| {
+                future::ready(
+                    !stopped.load(Ordering::Acquire)
+                        && !self.is_interrupted()
+                        && !p_rt.is_cancelled(),
+                )
+            })
+            .map(|item| {
+                let p_rt = &*p_rt;
+                async move {
+                    let action = match self.process_item(&item.source_item, p_rt, p).await {
+                        Ok(action) => action,
+                        Err(err) if p_rt.is_cancelled() => ItemAction::Cancelled(err),
+                        Err(err) => ItemAction::Error(err),
+                    };
+                    (item, action)
+                }
+            })
+            .buffered(parallelism);
+        while let Some((item, item_action)) = item_results.next().await {
             let item_pointer = item.item_pointer;
             let source_item = item.source_item;
-            let item_action = self.process_item(&source_item, &p_rt, p).await?;
-            match &item_action {
+            match item_action {
                 ItemAction::Skip(reason) => {
                     debug!("[item-skip] {} {:?} ", reason, source_item);
+                    self.on_item_skip(p, &source_item, &reason).await;
                     continue;
                 }
                 ItemAction::Error(err) => {
                     p_rt.processed_inc();
-                    // todo invoke on_item_error hooks
-                    if matches!(err, ProcessingError::NonRetryable { skip: true, .. }) {
+                    error!("[item-error] {} cause={}", source_item, err.message());
+                    p_rt.publish(
+                        p,
+                        ProcessEventKind::ItemFailed {
+                            item: (&source_item).into(),
+                            message: err.message().to_string(),
+                        },
+                    );
+                    let skip_on_error =
+                        matches!(err, ProcessingError::NonRetryable { skip: true, .. });
+                    // 不会跳过的Item不保存失败记录, 否则下次触发时会被当作已处理过滤掉
+                    if p.options.item_error_continue || skip_on_error {
+                        let content = ProcessingContent {
+                            id: None,
+                            processor_name: p.name.clone(),
+                            item_hash: source_item.hashing(),
+                            item_identity: source_item.identity.clone(),
+                            item_content: ItemContentLite {
+                                source_item: source_item.clone(),
+                                item_variables: PatternVariables::new(),
+                            },
+                            rename_times: 0,
+                            status: ProcessingStatus::Failure,
+                            failure_reason: Some(err.message().to_string()),
+                            created_at: OffsetDateTime::now_utc(),
+                            updated_at: None,
+                        };
+                        if let Err(e) = self.on_item_process_complete(p, &content, &vec![]).await {
+                            error!(
+                                "[item-error] Failed to save failure content {} cause={}",
+                                source_item,
+                                e.message()
+                            );
+                        }
+                    }
+                    p_rt.listener_context
+                        .lock()
+                        .record_error(&source_item, err.clone());
+                    self.on_item_error(p, p_rt, &source_item, &err).await;
+                    if p.options.item_error_continue {
+                        continue;
+                    }
+                    if skip_on_error {
                         warn!(
                             "[item-skip-on-error] 异常为可跳过类型 {} {}",
                             err.message(),
@@ -380,26 +952,63 @@ trait Process {
                         );
                         continue;
                     }
-                    warn!(
-                        "[item-non-retryable-error] 异常为不可跳过类型 {}, 退出本次触发处理",
-                        err.message()
-                    );
-                    break;
+                    // pointer停留在失败的Item之前, 下次触发时重新处理
+                    p_rt.pointer_frozen.store(true, Ordering::Release);
+                    if !stopped.swap(true, Ordering::AcqRel) {
+                        warn!(
+                            "[item-non-retryable-error] 异常为不可跳过类型 {}, 退出本次触发处理",
+                            err.message()
+                        );
+                    }
+                }
+                ItemAction::Cancelled(err) => {
+                    info!("[item-cancelled] {} cause={}", source_item, err.message());
+                    p_rt.pointer_frozen.store(true, Ordering::Release);
                 }
                 ItemAction::Success { content, files } => {
                     p_rt.processed_inc();
-                    self.on_item_success(p, &p_rt, &source_item, &item_pointer, &source_pointer)
+                    p_rt.listener_context.lock().record_item(content, files);
+                    self.on_item_success(p, p_rt, &source_item, &item_pointer, &source_pointer)
                         .await;
                 }
             }
         }
-        self.on_process_complete(p, &p_rt, source_pointer.clone())
+        // 已经全部处理完成, 释放对p_rt的借用
+        drop(item_results);
+        if self.is_interrupted() {
+            info!("[run-interrupted] {}", p.name);
+        }
+        if p_rt.is_cancelled() {
+            info!("[run-cancelled] {}", p.name);
+            if p.cancel_submitted.load(Ordering::Acquire) {
+                self.cancel_submitted_items(p, p_rt).await;
+            }
+        }
+        self.on_process_complete(p, p_rt, source_pointer.clone())
             .await;
         p_rt.process_end_at = Some(Instant::now());
         info!("[run-done] {} {}", p.name, p_rt.summary());
         Ok(())
     }
 
+    async fn fetch_items(
+        &self,
+        p: &SourceProcessor,
+        source_pointer: &Arc<dyn SourcePointer>,
+    ) -> Result<Vec<PointedItem>, ProcessingError> {
+        SourceProcessor::apply_retry(
+            || async {
+                p.source
+                    .fetch(source_pointer.clone(), p.options.fetch_limit)
+                    .await
+            },
+            "fetch-source-items",
+            &p.options.retry.fetch,
+            &self.cancel_token(p),
+        )
+        .await
+    }
+
     async fn get_source_state(
         &self,
         p: &SourceProcessor,
@@ -413,6 +1022,8 @@ trait Process {
                 processor_name: p.name.to_owned(),
                 source_id: p.source_id.to_owned(),
                 last_pointer: p.source.default_pointer().dump(),
+                retry_times: 0,
+                last_active_at: None,
             }))
     }
 
@@ -436,7 +1047,7 @@ trait Process {
         let source_pointer = self.get_source_pointer(p, &source_state).await?;
         let p_ctx = ProcessRuntime {
             trace_id: PROCESS_ID_GENERATOR
-                .fetch_add(i64::MIN, Ordering::Relaxed)
+                .fetch_add(1, Ordering::Relaxed)
                 .to_string(),
             mutex: Mutex::new(()),
             source_state,
@@ -444,11 +1055,18 @@ trait Process {
             process_submitted_items: RwLock::new(HashSet::new()),
             processed_count: AtomicU32::new(0),
             filter_count: AtomicU32::new(0),
+            fetched_count: 0,
+            started_at: OffsetDateTime::now_utc(),
             process_start_at: Some(start_time),
             process_end_at: None,
             fetch_start_at: None,
             fetch_end_at: None,
             cancel_items: vec![],
+            listener_context: parking_lot::Mutex::new(ListenerContext::new(p)),
+            cancel_token: self.cancel_token(p),
+            submitted_items: parking_lot::Mutex::new(vec![]),
+            publish_events: false,
+            pointer_frozen: AtomicBool::new(false),
         };
         Ok(p_ctx)
     }
@@ -460,29 +1078,33 @@ trait Process {
         p: &SourceProcessor,
     ) -> Result<ItemAction, ProcessingError> {
         let item_hash = source_item.hashing();
-        if rt.process_submitted_items.read().contains(&item_hash) {
+        if !rt.process_submitted_items.write().insert(item_hash) {
             rt.filter_inc();
             debug!("Source item duplicated: {:?} skipped", source_item);
             return Ok(ItemAction::Skip("Source item duplicated".to_string()));
         }
-        rt.process_submitted_items.write().insert(item_hash);
 
         debug!("[item-start] {}", source_item);
         let opt = &p.options;
+        let deadline = opt.item_timeout.map(|x| (Instant::now() + x, x));
         let item_rule = opt
             .item_rules
             .iter()
             .find(|x| x.matcher.matches(source_item));
         let item_strategy = item_rule.map(|x| &x.strategy);
-        let item_filters = item_strategy
-            .map(|x| x.item_filters.as_ref())
-            .flatten()
-            .unwrap_or(&opt.item_filters);
-        for filter in item_filters {
-            let filtered = !filter.filter(source_item).await;
+        for filter in self.select_item_filter(p, item_strategy) {
+            let filtered =
+                !within_deadline(deadline, "item-filter", filter.filter(source_item)).await?;
             if filtered {
                 debug!("[item-filtered] {}", source_item);
                 rt.filter_inc();
+                rt.publish(
+                    p,
+                    ProcessEventKind::ItemFiltered {
+                        item: source_item.into(),
+                        filter: filter.to_string(),
+                    },
+                );
                 return Ok(ItemAction::Skip(format!("Filtered by: {}", filter)));
             }
         }
@@ -493,20 +1115,30 @@ trait Process {
             .flatten()
             .unwrap_or(&opt.variable_providers);
         for x in variable_providers {
-            item_raw_vars.push((x.accuracy(), x.item_variables(source_item).await))
+            let vars =
+                within_deadline(deadline, "item-variables", x.item_variables(source_item)).await?;
+            item_raw_vars.push((x.accuracy(), vars))
         }
         let item_variables = opt.variable_aggregation.merge(&item_raw_vars);
 
-        let resolved_files = self.resolve_files(source_item, p).await?;
-        let mut file_contents = self
-            .process_source_files(
+        let resolved_files = within_deadline(
+            deadline,
+            "resolve-files",
+            self.resolve_files(source_item, p),
+        )
+        .await??;
+        let mut file_contents = within_deadline(
+            deadline,
+            "process-files",
+            self.process_source_files(
                 p,
                 source_item,
                 &item_variables,
                 resolved_files,
                 item_strategy,
-            )
-            .await?;
+            ),
+        )
+        .await??;
 
         let mut content_status = ProcessingStatus::WaitingToRename;
         let mut failure_reason: Option<String> = None;
@@ -517,46 +1149,114 @@ trait Process {
             status: content_status,
         };
         for x in &opt.item_content_filters {
-            let filtered = !x.filter(&item_content).await;
+            let filtered =
+                !within_deadline(deadline, "item-content-filter", x.filter(&item_content)).await?;
             if filtered {
                 debug!("[item-content-filtered] {}", source_item);
                 rt.filter_inc();
+                rt.publish(
+                    p,
+                    ProcessEventKind::ItemFiltered {
+                        item: source_item.into(),
+                        filter: x.to_string(),
+                    },
+                );
                 content_status = ProcessingStatus::Filtered;
                 failure_reason = Some(format!("Filtered by: {}", x));
                 break;
             }
         }
         //  ==== 数据准备阶段结束, 开始决定是否下载
-        if content_status != ProcessingStatus::Filtered {
+        let (should_download, mut content_status) = if content_status == ProcessingStatus::Filtered
+        {
+            (false, content_status)
+        } else {
+            // 并行处理时冲突检测到提交下载之间需要互斥
+            let _guard = rt.mutex.lock().await;
             // 1. 根据目标文件路径更新file_content状态
             self.update_file_content_status(p, source_item, &mut file_contents);
-        }
-        let (should_download, mut content_status, replace_files) = {
-            let _guard = rt.mutex.lock().await;
-            // preoccupiedTargetPath
-            // identifyFilesToReplace
-            let (should_download, content_status) =
-                self.probe_content_status(p, rt, source_item, &file_contents, &vec![]);
-            (should_download, content_status, vec![])
+            self.identify_files_to_replace(p, source_item, &item_variables, &mut file_contents)
+                .await?;
+            let probe = self
+                .probe_content_status(p, rt, source_item, &file_contents)
+                .await?;
+            if !probe.0 {
+                probe
+            } else {
+                // 提交下载前在存储中预占用目标路径, 其他处理器可能同时在处理相同的目标路径
+                let occupied = self
+                    .preoccupy_target_paths(p, source_item, &file_contents)
+                    .await?;
+                if !occupied.is_empty() {
+                    warn!("[target-preoccupied] {} paths:{:?}", source_item, occupied);
+                    (false, ProcessingStatus::TargetAlreadyExists)
+                } else {
+                    let files = file_contents
+                        .iter()
+                        .map(|f| to_source_file(f, f.file_download_path.clone()))
+                        .collect_vec();
+                    if let Err(e) = self.do_download(p, source_item, &file_contents).await {
+                        // 提交被取消时下载器可能已经收到了任务, 交给取消流程处理
+                        if rt.is_cancelled() && !self.rename_immediately(p) {
+                            rt.submitted_items.lock().push((source_item.clone(), files));
+                        }
+                        if let Err(release_err) =
+                            release_target_paths(p, &source_item.hashing()).await
+                        {
+                            error!(
+                                "[item-error] Failed to release target paths {} cause={}",
+                                source_item,
+                                release_err.message()
+                            );
+                        }
+                        return Err(e);
+                    }
+                    if !self.rename_immediately(p) {
+                        rt.submitted_items.lock().push((source_item.clone(), files));
+                    }
+                    rt.publish(
+                        p,
+                        ProcessEventKind::ItemSubmitted {
+                            item: source_item.into(),
+                        },
+                    );
+                    probe
+                }
+            }
         };
         let mut rename_times = 0;
-        if should_download {
-            self.do_download(p, source_item, &file_contents, &replace_files)
-                .await?;
-            let is_sync = !p.downloader.clone().as_async_downloader().is_ok();
-            if is_sync {
-                let movement_res = self
-                    .do_movement(p, source_item, &file_contents, &replace_files)
-                    .await;
-                let replacement_res = self
-                    .do_replacement(p, source_item, &file_contents, &replace_files)
-                    .await;
-                // 有点歧义后面重新定义
-                if movement_res.is_ok() || replacement_res.is_ok() {
+        if should_download && self.rename_immediately(p) {
+            let movement_res = self
+                .do_movement(p, source_item, &item_variables, &mut file_contents)
+                .await;
+            let result = match movement_res {
+                Ok(_) => {
+                    self.do_replacement(p, source_item, &item_variables, &mut file_contents)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(_) => {
                     content_status = ProcessingStatus::Renamed;
                     rename_times = 1;
-                } else {
+                    rt.publish(
+                        p,
+                        ProcessEventKind::ItemRenamed {
+                            item: source_item.into(),
+                        },
+                    );
+                }
+                Err(e) => {
                     content_status = ProcessingStatus::Failure;
+                    failure_reason = Some(e.message().to_string());
+                    rt.publish(
+                        p,
+                        ProcessEventKind::ItemFailed {
+                            item: source_item.into(),
+                            message: e.message().to_string(),
+                        },
+                    );
                 }
             }
         }
@@ -588,36 +1288,158 @@ trait Process {
 
     async fn do_movement(
         &self,
-        _p: &SourceProcessor,
-        _source_item: &SourceItem,
-        _file_contents: &Vec<FileContent>,
-        _replace_files: &Vec<FileContent>,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        item_variables: &PatternVariables,
+        file_contents: &mut Vec<FileContent>,
     ) -> Result<(), ProcessingError> {
-        Ok(())
+        move_files(p, source_item, item_variables, file_contents).await
     }
 
+    /// 通过FileMover替换状态为ReadyReplace的文件, 被替换的文件在原Item中标记为Replaced
     async fn do_replacement(
-        &self,
-        _p: &SourceProcessor,
-        _source_item: &SourceItem,
-        _file_contents: &Vec<FileContent>,
-        _replace_files: &Vec<FileContent>,
-    ) -> Result<(), ProcessingError> {
-        Ok(())
-    }
-
-    async fn do_download(
         &self,
         p: &SourceProcessor,
         source_item: &SourceItem,
-        file_contents: &Vec<FileContent>,
-        replace_files: &Vec<FileContent>,
-    ) -> Result<(), ProcessingError> {
-        let all_files: Vec<SourceFileRef> = file_contents
+        item_variables: &PatternVariables,
+        file_contents: &mut Vec<FileContent>,
+    ) -> Result<(), ProcessingError> {
+        let replace_indices = file_contents
             .iter()
-            .chain(replace_files.iter())
-            .map(Into::into)
+            .enumerate()
+            .filter(|(_, f)| f.status == ReadyReplace)
+            .map(|(idx, _)| idx)
             .collect_vec();
+        if replace_indices.is_empty() {
+            return Ok(());
+        }
+        let item_content = ItemContent {
+            source_item,
+            file_contents,
+            item_variables,
+            status: ProcessingStatus::WaitingToRename,
+        };
+        p.file_mover.replace(&item_content)?;
+
+        for idx in replace_indices {
+            let f = &mut file_contents[idx];
+            f.status = Replace;
+            let target_path = f.target_path().to_string_lossy();
+            info!("[file-replaced] {}", target_path);
+            if let Err(e) = mark_file_replaced(p, &target_path).await {
+                warn!(
+                    "Failed to mark replaced file {} cause={}",
+                    target_path,
+                    e.message()
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// 目标文件已存在时询问FileReplacementDecider是否替换, 需要替换的文件状态改为ReadyReplace
+    async fn identify_files_to_replace(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        item_variables: &PatternVariables,
+        file_contents: &mut Vec<FileContent>,
+    ) -> Result<(), ProcessingError> {
+        let mut replace_indices = vec![];
+        let mut before_items: HashMap<i64, (ProcessingContent, Vec<FileContent>)> = HashMap::new();
+        let current = ItemContent {
+            source_item,
+            file_contents,
+            item_variables,
+            status: ProcessingStatus::WaitingToRename,
+        };
+        for (idx, f) in file_contents.iter().enumerate() {
+            if f.status != TargetExists {
+                continue;
+            }
+            let Some(exist_target_path) = &f.exist_target_path else {
+                continue;
+            };
+            let Some(decider) = self.select_file_replacement_decider(p, f, file_contents.len())
+            else {
+                continue;
+            };
+
+            let exist_target_path = exist_target_path.to_string_lossy();
+            let before_id = match p
+                .processing_storage
+                .find_content_by_target_path(&exist_target_path)
+                .await
+                .map_err(|x| ProcessingError::non_retryable(x.message))?
+            {
+                Some(content) => {
+                    let content_id = content.id.unwrap_or_default();
+                    if let Entry::Vacant(entry) = before_items.entry(content_id) {
+                        let files = p
+                            .processing_storage
+                            .find_file_contents(content_id)
+                            .await
+                            .map_err(|x| ProcessingError::non_retryable(x.message))?
+                            .map(|bytes| decode_files_from_compressed(&bytes))
+                            .transpose()?
+                            .unwrap_or_default();
+                        entry.insert((content, files));
+                    }
+                    Some(content_id)
+                }
+                None => None,
+            };
+
+            let existing_file = p.file_mover.path_metadata(&exist_target_path);
+            let replace = match before_id.and_then(|id| before_items.get(&id)) {
+                Some((content, files)) => {
+                    let failure_reason = content.failure_reason.as_deref();
+                    let before = to_in_processing_item(content, files, &failure_reason);
+                    decider.should_replace(&current, Some(&before), &existing_file)
+                }
+                None => decider.should_replace(&current, None, &existing_file),
+            };
+            debug!(
+                "[file-replacement] {} replace={} decider={}",
+                exist_target_path, replace, decider
+            );
+            if replace {
+                replace_indices.push(idx);
+            }
+        }
+        for idx in replace_indices {
+            file_contents[idx].status = ReadyReplace;
+        }
+        Ok(())
+    }
+
+    fn select_file_replacement_decider(
+        &self,
+        p: &SourceProcessor,
+        file: &FileContent,
+        file_count: usize,
+    ) -> Option<Arc<dyn FileReplacementDecider>> {
+        let path = file
+            .file_download_path
+            .strip_prefix(&file.download_path)
+            .unwrap_or(&file.file_download_path)
+            .to_path_buf();
+        let source_file = to_source_file(file, path);
+        p.options
+            .file_rules
+            .iter()
+            .find(|rule| rule.matcher.matches(&source_file, file_count))
+            .and_then(|rule| rule.strategy.file_replacement_decider.clone())
+            .or_else(|| p.options.file_replacement_decider.clone())
+    }
+
+    async fn do_download(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        file_contents: &[FileContent],
+    ) -> Result<(), ProcessingError> {
+        let all_files: Vec<SourceFileRef> = file_contents.iter().map(Into::into).collect_vec();
 
         let (direct_files, download_files): (Vec<_>, Vec<_>) =
             all_files.into_iter().partition(|f| f.data.is_some());
@@ -665,14 +1487,44 @@ trait Process {
             tags: options.tags.as_deref(),
             headers,
         };
-        p.downloader.submit(&opt).await?;
-        Ok(())
+        SourceProcessor::apply_retry(
+            || p.downloader.submit(&opt),
+            "submit-download",
+            &p.options.retry.download,
+            &self.cancel_token(p),
+        )
+        .await
+    }
+
+    /// 运行被取消时调用
+    async fn cancel_submitted_items(&self, p: &SourceProcessor, rt: &ProcessRuntime) {
+        cancel_submitted_items(p, rt).await
+    }
+
+    /// 提交下载前预占用目标路径, 重命名或取消后释放, 返回被其他Item占用的路径
+    async fn preoccupy_target_paths(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        file_contents: &[FileContent],
+    ) -> Result<Vec<String>, ProcessingError> {
+        preoccupy_target_paths(p, source_item, file_contents).await
+    }
+
+    /// 目标路径已存在的文件, key为目标路径, value为已存在的路径
+    fn detect_exists_targets(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        file_contents: &Vec<FileContent>,
+    ) -> HashMap<PathBuf, PathBuf> {
+        detect_exists_targets(p, source_item, file_contents)
     }
 
     fn update_file_content_status(
         &self,
-        _p: &SourceProcessor,
-        _source_item: &SourceItem,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
         file_contents: &mut Vec<FileContent>,
     ) {
         let conflict_indices: HashSet<usize> = {
@@ -696,26 +1548,7 @@ trait Process {
                 .collect()
         };
 
-        let exists_mapping: HashMap<&PathBuf, Option<&PathBuf>> = {
-            let mut mapping: HashMap<&PathBuf, Option<&PathBuf>> = HashMap::new();
-            // 暂时不实现
-            // let exists_results = p.file_mover.exists(&target_paths);
-            // for ((_, path, _), exists) in undetected_info.iter().zip(exists_results) {
-            //     mapping.insert(path.clone(), if exists { Some(path.clone()) } else { None });
-            // }
-
-            // file_exists_detector.exists (如果不是 SimpleFileExistsDetector)
-            // let detector_results = p.options.file_exists_detector.exists(
-            //     p.file_mover.as_ref(),
-            //     source_item,
-            //     file_contents,
-            // );
-            // for (path, exists_path) in detector_results {
-            //     // 如果 file_mover 认为已存在，detector 不能覆盖
-            //     mapping.entry(path).or_insert(exists_path);
-            // }
-            mapping
-        };
+        let exists_mapping = self.detect_exists_targets(p, source_item, file_contents);
 
         for (idx, x) in file_contents.iter_mut().enumerate() {
             if x.status != Undetected {
@@ -732,7 +1565,7 @@ trait Process {
             }
 
             // 3. 目标已存在
-            if let Some(Some(exists_path)) = exists_mapping.get(x.target_path()) {
+            if let Some(exists_path) = exists_mapping.get(x.target_path()) {
                 x.status = TargetExists;
                 x.exist_target_path = Some(exists_path.to_path_buf());
                 continue;
@@ -741,22 +1574,21 @@ trait Process {
         }
     }
 
-    fn probe_content_status(
+    async fn probe_content_status(
         &self,
         p: &SourceProcessor,
         rt: &ProcessRuntime,
         source_item: &SourceItem,
-        files: &Vec<FileContent>,
-        replace_files: &Vec<FileContent>,
-    ) -> (bool, ProcessingStatus) {
+        files: &[FileContent],
+    ) -> Result<(bool, ProcessingStatus), ProcessingError> {
         if files.is_empty() {
-            return (false, ProcessingStatus::NoFiles);
+            return Ok((false, ProcessingStatus::NoFiles));
         };
-        if !replace_files.is_empty() {
-            return (true, ProcessingStatus::WaitingToRename);
+        if files.iter().any(|x| x.status == ReadyReplace) {
+            return Ok((true, ProcessingStatus::WaitingToRename));
         };
         if rt.cancel_items.contains(source_item) {
-            return (false, ProcessingStatus::Cancelled);
+            return Ok((false, ProcessingStatus::Cancelled));
         }
         // 预防这一批次的Item有相同的目标，并且是AsyncDownloader的情况下会重复下载
         if files.iter().all(|x| x.status == TargetExists) {
@@ -765,7 +1597,16 @@ trait Process {
                 source_item,
                 files.iter().map(|f| f.target_path.get()).collect_vec()
             );
-            return (false, ProcessingStatus::TargetAlreadyExists);
+            return Ok((false, ProcessingStatus::TargetAlreadyExists));
+        }
+        // 目标路径被其他还未重命名的Item预占用
+        let preoccupied = find_preoccupied_paths(p, source_item, files).await?;
+        if !preoccupied.is_empty() {
+            warn!(
+                "[target-preoccupied] {} paths:{:?}",
+                source_item, preoccupied
+            );
+            return Ok((false, ProcessingStatus::TargetAlreadyExists));
         }
 
         let file_download_paths = files.iter().map(|f| &f.file_download_path).collect_vec();
@@ -776,9 +1617,9 @@ trait Process {
             .all(|x| x);
         if all_exists {
             let is_async = p.downloader.clone().as_async_downloader().is_ok();
-            return (is_async, ProcessingStatus::WaitingToRename);
+            return Ok((is_async, ProcessingStatus::WaitingToRename));
         }
-        (true, ProcessingStatus::WaitingToRename)
+        Ok((true, ProcessingStatus::WaitingToRename))
     }
 
     async fn resolve_files(
@@ -786,13 +1627,16 @@ trait Process {
         source_item: &SourceItem,
         p: &SourceProcessor,
     ) -> Result<Vec<SourceFile>, ProcessingError> {
-        let original_files = p
-            .item_file_resolver
-            .resolve_files(source_item)
-            .await
-            .into_iter()
-            .filter(|x| p.options.source_file_filters.iter().all(|y| y.filter(x)))
-            .collect::<Vec<_>>();
+        let original_files = SourceProcessor::apply_retry(
+            || p.item_file_resolver.resolve_files(source_item),
+            "resolve-files",
+            &p.options.retry.resolve,
+            &self.cancel_token(p),
+        )
+        .await?
+        .into_iter()
+        .filter(|x| p.options.source_file_filters.iter().all(|y| y.filter(x)))
+        .collect::<Vec<_>>();
         let mut counts: HashMap<&Path, usize> = HashMap::new();
         for f in &original_files {
             let count = counts.entry(f.path.as_ref()).or_insert(0);
@@ -936,11 +1780,49 @@ trait Process {
 }
 
 #[allow(dead_code)]
-struct NormalProcess {}
+struct NormalProcess {
+    trigger_source: &'static str,
+}
 
 impl Process for NormalProcess {
-    fn select_item_filter<'a>(&self, p: &'a SourceProcessor) -> &'a Vec<Arc<dyn SourceItemFilter>> {
-        &p.options.item_filters
+    fn trigger_source(&self) -> Option<&str> {
+        Some(self.trigger_source)
+    }
+
+    /// 记录Source连续获取失败的次数, 成功后重置
+    async fn on_fetch_complete(
+        &self,
+        p: &SourceProcessor,
+        ctx: &mut ProcessRuntime,
+        result: &Result<Vec<PointedItem>, ProcessingError>,
+    ) {
+        let state = &mut ctx.source_state;
+        let failures = state.retry_times;
+        match result {
+            Ok(_) => state.retry_times = 0,
+            Err(_) if ctx.cancel_token.is_cancelled() => return,
+            Err(e) => {
+                state.retry_times += 1;
+                warn!(
+                    "[source-fetch-failed] {} consecutive failures:{} cause={}",
+                    p.name,
+                    state.retry_times,
+                    e.message()
+                );
+            }
+        }
+        state.last_active_at = Some(OffsetDateTime::now_utc());
+        if failures != state.retry_times {
+            match p
+                .processing_storage
+                .save_processor_source_state(state)
+                .await
+            {
+                Ok(saved) => state.id = saved.id,
+                Err(e) => error!("Failed to save source state: {:?}", e.message),
+            }
+        }
+        p.update_source_health(state);
     }
 
     async fn on_process_complete(
@@ -949,7 +1831,7 @@ impl Process for NormalProcess {
         ctx: &ProcessRuntime,
         pointer: Arc<dyn SourcePointer>,
     ) {
-        // TODO invoke hooks
+        notify_process_completed(p, &ctx.listener_context.lock());
         // 第二个条件待定
         if p.options.pointer_batch_mode || ctx.processed_count.load(Ordering::Acquire) == 0 {
             p.save_source_state(&ProcessorSourceState {
@@ -977,26 +1859,17 @@ impl Process for NormalProcess {
         if !p.options.save_processing_content {
             return Ok(());
         }
-        // 事务?
-        let content_id = p
-            .processing_storage
-            .save_processing_content(processing_content)
-            .await
-            .map_err(|x| {
-                ProcessingError::non_retryable(format!("Failed to save item content {}", x.message))
-            })?;
+        save_item_content(p, processing_content, files).await
+    }
 
-        let bytes = encode_files_and_compress(&files)?;
-        p.processing_storage
-            .save_file_contents(content_id, bytes)
-            .await
-            .map_err(|x| {
-                ProcessingError::non_retryable(format!(
-                    "Failed to save file contents {}",
-                    x.message
-                ))
-            })?;
-        Ok(())
+    async fn on_item_error(
+        &self,
+        p: &SourceProcessor,
+        ctx: &ProcessRuntime,
+        source_item: &SourceItem,
+        err: &ProcessingError,
+    ) {
+        notify_item_error(p, &ctx.listener_context.lock(), source_item, err);
     }
 
     async fn on_item_success(
@@ -1007,7 +1880,10 @@ impl Process for NormalProcess {
         item_pointer: &Arc<dyn ItemPointer>,
         source_pointer: &Arc<dyn SourcePointer>,
     ) {
-        // TODO invoke hooks
+        notify_item_processed(p, &ctx.listener_context.lock(), source_item);
+        if ctx.pointer_frozen.load(Ordering::Acquire) {
+            return;
+        }
         source_pointer.update(source_item, item_pointer);
         if !p.options.pointer_batch_mode {
             let new_pointer = source_pointer.dump();
@@ -1023,120 +1899,2158 @@ impl Process for NormalProcess {
 
 impl NormalProcess {}
 
-pub fn encode_files_and_compress(files: &Vec<FileContent>) -> Result<Vec<u8>, ProcessingError> {
-    let bytes = if files.is_empty() {
-        vec![]
-    } else {
-        let bytes = postcard::to_stdvec(&files).map_err(|x| {
-            ProcessingError::non_retryable(format!("Failed to desc file content {}", x.to_string()))
+async fn save_item_content(
+    p: &SourceProcessor,
+    processing_content: &ProcessingContent,
+    files: &Vec<FileContent>,
+) -> Result<(), ProcessingError> {
+    // 事务?
+    let content_id = p
+        .processing_storage
+        .save_processing_content(processing_content)
+        .await
+        .map_err(|x| {
+            ProcessingError::non_retryable(format!("Failed to save item content {}", x.message))
         })?;
-        // 压缩比待定
-        let level = 6;
-        zstd::encode_all(Cursor::new(bytes), level).map_err(|x| {
-            ProcessingError::non_retryable(format!(
-                "Failed to compress file content {}",
-                x.to_string()
-            ))
-        })?
+
+    let bytes = encode_files_and_compress(files)?;
+    p.processing_storage
+        .save_file_contents(content_id, bytes)
+        .await
+        .map_err(|x| {
+            ProcessingError::non_retryable(format!("Failed to save file contents {}", x.message))
+        })?;
+    if processing_content.status == ProcessingStatus::Renamed {
+        let target_paths = files
+            .iter()
+            .filter(|f| matches!(f.status, Normal | Replace))
+            .map(|f| f.target_path().to_string_lossy().to_string())
+            .collect_vec();
+        p.processing_storage
+            .save_file_targets(content_id, target_paths)
+            .await
+            .map_err(|x| {
+                ProcessingError::non_retryable(format!("Failed to save file targets {}", x.message))
+            })?;
+    }
+    if processing_content.status != ProcessingStatus::WaitingToRename {
+        release_target_paths(p, &processing_content.item_hash).await?;
+    }
+    Ok(())
+}
+
+async fn save_processor_run(
+    p: &SourceProcessor,
+    rt: &ProcessRuntime,
+    trigger_source: &str,
+    result: &Result<(), ProcessingError>,
+) {
+    let outcome = match result {
+        _ if rt.is_cancelled() => RunOutcome::Cancelled,
+        Ok(_) => RunOutcome::Success,
+        Err(_) => RunOutcome::Failure,
     };
-    Ok(bytes)
+    let fetch_took_ms = match (rt.fetch_start_at, rt.fetch_end_at) {
+        (Some(start), Some(end)) => Some(end.duration_since(start).as_millis() as u64),
+        _ => None,
+    };
+    let run = ProcessorRun {
+        id: None,
+        trace_id: rt.trace_id.clone(),
+        processor_name: p.name.clone(),
+        trigger_source: trigger_source.to_string(),
+        started_at: rt.started_at,
+        ended_at: OffsetDateTime::now_utc(),
+        fetched_count: rt.fetched_count,
+        processed_count: rt.processed_count.load(Ordering::Acquire),
+        filtered_count: rt.filter_count.load(Ordering::Acquire),
+        fetch_took_ms,
+        outcome,
+        error: result.as_ref().err().map(|e| e.message().to_string()),
+    };
+    if let Err(e) = p.processing_storage.save_processor_run(&run).await {
+        error!("[run-save-error] {} cause={}", p.name, e.message);
+    }
 }
 
-#[allow(dead_code)]
-pub fn decode_files_from_compressed(bytes: &[u8]) -> Result<Vec<FileContent>, ProcessingError> {
-    if bytes.is_empty() {
-        return Ok(vec![]);
+/// 从下载器中取消本次运行提交的Item, 并标记为Cancelled
+async fn cancel_submitted_items(p: &SourceProcessor, rt: &ProcessRuntime) {
+    let items = std::mem::take(&mut *rt.submitted_items.lock());
+    for (item, files) in items {
+        if let Err(e) = p.downloader.cancel(&item, &files).await {
+            error!("[item-cancel-error] {} cause={}", item, e.message());
+            continue;
+        }
+        let result = async {
+            let content = p
+                .processing_storage
+                .find_by_name_and_hash(&p.name, &item.hashing())
+                .await
+                .map_err(|x| ProcessingError::non_retryable(x.message))?;
+            if let Some(mut content) = content {
+                content.status = ProcessingStatus::Cancelled;
+                content.updated_at = Some(OffsetDateTime::now_utc());
+                p.processing_storage
+                    .save_processing_content(&content)
+                    .await
+                    .map_err(|x| ProcessingError::non_retryable(x.message))?;
+            }
+            release_target_paths(p, &item.hashing()).await
+        }
+        .await;
+        match result {
+            Ok(_) => info!("[item-cancelled] {}", item),
+            Err(e) => error!("[item-cancel-error] {} cause={}", item, e.message()),
+        }
     }
-    let decompressed = zstd::decode_all(bytes).map_err(|x| {
-        ProcessingError::non_retryable(format!(
-            "Failed to decompress file content {}",
-            x.to_string()
-        ))
-    })?;
-    let files: Vec<FileContent> = postcard::from_bytes(&decompressed).map_err(|x| {
-        ProcessingError::non_retryable(format!(
-            "Failed to deserialize file content {}",
-            x.to_string()
+}
+
+/// 超时后返回可重试的异常, 只用于Item的数据准备阶段, 提交下载之后的阶段不会被中断
+async fn within_deadline<T>(
+    deadline: Option<(Instant, Duration)>,
+    stage: &str,
+    fut: impl Future<Output = T>,
+) -> Result<T, ProcessingError> {
+    let Some((deadline, timeout)) = deadline else {
+        return Ok(fut.await);
+    };
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    tokio::time::timeout(remaining, fut).await.map_err(|_| {
+        ProcessingError::retryable(format!(
+            "Item processing timed out after {} at stage {}",
+            format_duration(timeout),
+            stage
         ))
-    })?;
-    Ok(files)
+    })
 }
 
-#[allow(dead_code)]
-struct DryRunProcess {}
-#[allow(dead_code)]
-struct Reprocess {}
-#[allow(dead_code)]
-struct FixedItemProcess {}
+fn preoccupy_paths_of(files: &[FileContent]) -> Vec<String> {
+    files
+        .iter()
+        .filter(|f| matches!(f.status, Normal | ReadyReplace))
+        .map(|f| f.target_path().to_string_lossy().to_string())
+        .collect_vec()
+}
 
-#[cfg(test)]
-mod test {
-    use crate::config::ConfigOperator;
-    use crate::processor_test_support::test_support::*;
-    use jsonpath_rust::JsonPath;
-    use source_downloader_sdk::component::ProcessTask;
+async fn preoccupy_target_paths(
+    p: &SourceProcessor,
+    source_item: &SourceItem,
+    files: &[FileContent],
+) -> Result<Vec<String>, ProcessingError> {
+    let item_hash = source_item.hashing();
+    let paths = preoccupy_paths_of(files)
+        .into_iter()
+        .map(|path| ProcessingTargetPath {
+            path,
+            processor_name: p.name.clone(),
+            item_hash: item_hash.clone(),
+        })
+        .collect_vec();
+    let occupied = p
+        .processing_storage
+        .reserve_paths(paths)
+        .await
+        .map_err(|x| {
+            ProcessingError::non_retryable(format!(
+                "Failed to preoccupy target paths {}",
+                x.message
+            ))
+        })?;
+    Ok(occupied.into_iter().map(|x| x.path).collect())
+}
 
-    // <editor-fold desc="Sync item content tests">
-    #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn sync_downloader_case() {
-        let cfg = cfg();
-        let pm = processor_manager().await;
-        let storage = storage().await;
-        for (name, case) in CASES.iter() {
-            pm.create_processor(
-                &cfg.get_processor_config(name)
-                    .expect("Failed to get processor config"),
-            );
-            let p = assert_processor(name, pm);
-            let root_path = V_PATH
-                .join(format!("/{}", name))
-                .expect("Failed to join path");
-            apply_case_files(&root_path, &case.files);
+/// 被其他Item预占用的目标路径, 同一个Item的不算
+async fn find_preoccupied_paths(
+    p: &SourceProcessor,
+    source_item: &SourceItem,
+    files: &[FileContent],
+) -> Result<Vec<String>, ProcessingError> {
+    let paths = preoccupy_paths_of(files);
+    if paths.is_empty() {
+        return Ok(vec![]);
+    }
+    let item_hash = source_item.hashing();
+    let preoccupied = p
+        .processing_storage
+        .find_paths(&paths)
+        .await
+        .map_err(|x| ProcessingError::non_retryable(x.message))?
+        .into_iter()
+        .filter(|x| x.processor_name != p.name || x.item_hash != item_hash)
+        .map(|x| x.path)
+        .collect_vec();
+    Ok(preoccupied)
+}
 
-            let result = p.run().await;
-            assert!(result.is_ok());
+async fn release_target_paths(p: &SourceProcessor, item_hash: &str) -> Result<(), ProcessingError> {
+    p.processing_storage
+        .delete_paths_by_item(&p.name, item_hash)
+        .await
+        .map_err(|x| {
+            ProcessingError::non_retryable(format!("Failed to release target paths {}", x.message))
+        })
+}
 
-            let content = build_result_json(storage, name).await;
-            for (assert_idx, assertion) in case.assertions.iter().enumerate() {
-                let selection = content.query(&assertion.select).unwrap_or_default();
-                if !assertion.allow_empty && selection.is_empty() {
-                    let err = AssertionError::new("Selection result is empty".to_string())
-                        .with_context(format!("case: {}", name))
-                        .with_context(format!("assertion #{}", assert_idx))
-                        .with_context(format!("select: {}", assertion.select));
-                    panic!("{}", err)
+/// 移动状态为Normal的文件到目标路径, 任意文件失败时回滚已移动的文件
+async fn move_files(
+    p: &SourceProcessor,
+    source_item: &SourceItem,
+    item_variables: &PatternVariables,
+    file_contents: &mut Vec<FileContent>,
+) -> Result<(), ProcessingError> {
+    let movable = file_contents
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.status == Normal)
+        .map(|(idx, _)| idx)
+        .collect_vec();
+    if movable.is_empty() {
+        return Ok(());
+    }
+
+    let mut created_dirs: HashSet<&Path> = HashSet::new();
+    let mut failures: Vec<(usize, String)> = vec![];
+    for idx in &movable {
+        let dir = file_contents[*idx].target_save_path.as_path();
+        if !created_dirs.insert(dir) {
+            continue;
+        }
+        if let Err(e) = p.file_mover.create_directories(&dir.to_string_lossy()) {
+            failures.push((*idx, e.message().to_string()));
+            break;
+        }
+    }
+
+    let mut moved: Vec<usize> = vec![];
+    if failures.is_empty() {
+        if p.file_mover.is_supported_batch_move() {
+            let movable_files = movable
+                .iter()
+                .map(|idx| file_contents[*idx].clone())
+                .collect_vec();
+            let item_content = ItemContent {
+                source_item,
+                file_contents: &movable_files,
+                item_variables,
+                status: ProcessingStatus::WaitingToRename,
+            };
+            let result = SourceProcessor::apply_retry(
+                || async { p.file_mover.batch_move(&item_content) },
+                "batch-move",
+                &p.options.retry.movement,
+                &p.cancel_token(),
+            )
+            .await;
+            if let Err(e) = result {
+                let target_paths = movable
+                    .iter()
+                    .map(|idx| file_contents[*idx].target_path())
+                    .collect_vec();
+                let exists = p.file_mover.exists(&target_paths);
+                for (idx, exists) in movable.iter().zip(exists) {
+                    if exists {
+                        moved.push(*idx);
+                    }
+                    failures.push((*idx, e.message().to_string()));
                 }
-                for (node_idx, node) in selection.iter().enumerate() {
-                    if let Err(err) = apply_assertion(node, &assertion.asserts) {
-                        let err = err
-                            .with_context(format!("case: {}", name))
-                            .with_context(format!("assertion #{}", assert_idx))
-                            .with_context(format!("select: {}", assertion.select))
-                            .with_context(format!("node index: {}", node_idx))
-                            .with_context(format!("content #{}", node));
-                        panic!("{}", err);
+            }
+        } else {
+            for idx in &movable {
+                let f = &file_contents[*idx];
+                let source_file = to_source_file(f, f.file_download_path.clone());
+                let target = f.target_path().to_string_lossy();
+                let result = SourceProcessor::apply_retry(
+                    || async { p.file_mover.move_file(&source_file, &target) },
+                    "move-file",
+                    &p.options.retry.movement,
+                    &p.cancel_token(),
+                )
+                .await;
+                match result {
+                    Ok(_) => moved.push(*idx),
+                    Err(e) => {
+                        failures.push((*idx, e.message().to_string()));
+                        break;
                     }
                 }
             }
         }
     }
-    // </editor-fold>
+    if failures.is_empty() {
+        return Ok(());
+    }
 
-    // <editor-fold desc="Flow control tests">
-    #[tokio::test]
-    #[tracing_test::traced_test]
-    async fn flow_ctr_retry_then_ok() {
-        let name = "flow_ctr_retry_then_ok";
-        let cfg = cfg()
-            .get_processor_config(name)
-            .expect("Failed to get processor config");
-        let pm = processor_manager().await;
-        pm.create_processor(&cfg);
-        let p = assert_processor(name, pm);
-        let r = p.run().await;
-        assert!(r.is_ok());
-        assert!(logs_contain("Retrying fetch-source-items delay"));
+    for idx in moved.iter().rev() {
+        let f = &file_contents[*idx];
+        let download_path = &f.file_download_path;
+        match undo_move_file(p, f, f.target_path(), download_path) {
+            Ok(_) => info!(
+                "[movement-rollback] {} -> {}",
+                f.target_path().display(),
+                download_path.display()
+            ),
+            Err(e) => error!(
+                "[movement-rollback] Failed to rollback {} -> {} cause={}",
+                f.target_path().display(),
+                download_path.display(),
+                e.message()
+            ),
+        }
+    }
+
+    let reason = failures
+        .into_iter()
+        .map(|(idx, message)| {
+            let f = &mut file_contents[idx];
+            f.errors.push(message.clone());
+            format!("{}: {}", f.file_download_path.display(), message)
+        })
+        .join("; ");
+    warn!("[movement-failed] {} {}", source_item, reason);
+    Err(ProcessingError::non_retryable(format!(
+        "Failed to move files {}",
+        reason
+    )))
+}
+
+/// 撤销一次move_file, 源文件被保留时删除目标文件, 否则移动回原来的位置
+fn undo_move_file(
+    p: &SourceProcessor,
+    f: &FileContent,
+    moved_to: &Path,
+    origin: &Path,
+) -> Result<(), ProcessingError> {
+    if p.file_mover.is_source_retained() {
+        return p.file_mover.delete_file(&moved_to.to_string_lossy());
+    }
+    let source_file = to_source_file(f, moved_to.to_path_buf());
+    p.file_mover
+        .move_file(&source_file, &origin.to_string_lossy())
+}
+
+async fn mark_file_replaced(p: &SourceProcessor, target_path: &str) -> Result<(), ProcessingError> {
+    let Some(before) = p
+        .processing_storage
+        .find_content_by_target_path(target_path)
+        .await
+        .map_err(|x| ProcessingError::non_retryable(x.message))?
+    else {
+        return Ok(());
+    };
+    let Some(content_id) = before.id else {
+        return Ok(());
+    };
+    let Some(bytes) = p
+        .processing_storage
+        .find_file_contents(content_id)
+        .await
+        .map_err(|x| ProcessingError::non_retryable(x.message))?
+    else {
+        return Ok(());
+    };
+    let mut files = decode_files_from_compressed(&bytes)?;
+    for f in files.iter_mut() {
+        if f.target_path().to_string_lossy() == target_path {
+            f.status = Replaced;
+        }
+    }
+    p.processing_storage
+        .save_file_contents(content_id, encode_files_and_compress(&files)?)
+        .await
+        .map_err(|x| ProcessingError::non_retryable(x.message))
+}
+
+/// 目标路径已存在的文件, 先由FileMover判断, FileMover认为不存在的再交给FileExistsDetector
+fn detect_exists_targets(
+    p: &SourceProcessor,
+    source_item: &SourceItem,
+    file_contents: &Vec<FileContent>,
+) -> HashMap<PathBuf, PathBuf> {
+    let target_paths = file_contents
+        .iter()
+        .filter(|f| f.status == Undetected)
+        .map(|f| f.target_path())
+        .collect_vec();
+    if target_paths.is_empty() {
+        return HashMap::new();
+    }
+    let mut mapping: HashMap<PathBuf, PathBuf> = target_paths
+        .iter()
+        .zip(p.file_mover.exists(&target_paths))
+        .filter(|(_, exists)| *exists)
+        .map(|(path, _)| (path.to_path_buf(), path.to_path_buf()))
+        .collect();
+
+    let detector = p.options.file_exists_detector.as_ref();
+    // SimpleFileExistsDetector的结果与FileMover一致
+    if (detector as &dyn Any).is::<SimpleFileExistsDetector>() {
+        return mapping;
+    }
+    let detected = detector.exists(p.file_mover.as_ref(), source_item, file_contents);
+    for (path, exists_path) in detected {
+        if let Some(exists_path) = exists_path {
+            mapping
+                .entry(path.to_path_buf())
+                .or_insert_with(|| exists_path.to_path_buf());
+        }
+    }
+    mapping
+}
+
+/// 提供给ProcessListener的上下文, 记录本次处理过的Item
+struct ListenerContext {
+    processor: ProcessorInfo,
+    processed_items: Vec<SourceItem>,
+    item_contents: HashMap<String, ListenerItem>,
+    errors: Vec<(SourceItem, ProcessingError)>,
+}
+
+/// InProcessingItem需要借用failure_reason, 和content存放在一起
+#[self_referencing]
+struct ListenerItem {
+    content: ProcessingContent,
+    files: Vec<FileContent>,
+    #[borrows(content)]
+    #[covariant]
+    failure_reason: Option<&'this str>,
+}
+
+impl ListenerContext {
+    fn new(p: &SourceProcessor) -> Self {
+        ListenerContext {
+            processor: ProcessorInfo {
+                name: p.name.clone(),
+                download_path: p.download_path.to_string_lossy().to_string(),
+                source_save_path: p.save_path.to_string_lossy().to_string(),
+                tags: p.tags.clone(),
+                category: p.category.clone(),
+            },
+            processed_items: vec![],
+            item_contents: HashMap::new(),
+            errors: vec![],
+        }
+    }
+
+    fn record_item(&mut self, content: ProcessingContent, files: Vec<FileContent>) {
+        self.processed_items
+            .push(content.item_content.source_item.clone());
+        let item_hash = content.item_hash.clone();
+        let item = ListenerItemBuilder {
+            content,
+            files,
+            failure_reason_builder: |content| content.failure_reason.as_deref(),
+        }
+        .build();
+        self.item_contents.insert(item_hash, item);
+    }
+
+    fn record_error(&mut self, source_item: &SourceItem, err: ProcessingError) {
+        self.processed_items.push(source_item.clone());
+        self.errors.push((source_item.clone(), err));
+    }
+}
+
+impl ProcessContext for ListenerContext {
+    fn processor(&self) -> &ProcessorInfo {
+        &self.processor
+    }
+
+    fn processed_items(&self) -> &Vec<SourceItem> {
+        &self.processed_items
+    }
+
+    fn get_item_content(&self, item: &SourceItem) -> Option<InProcessingItem<'_>> {
+        self.item_contents.get(&item.hashing()).map(|x| {
+            to_in_processing_item(
+                x.borrow_content(),
+                x.borrow_files(),
+                x.borrow_failure_reason(),
+            )
+        })
+    }
+
+    fn has_error(&self) -> bool {
+        !self.errors.is_empty()
+            || self
+                .item_contents
+                .values()
+                .any(|x| x.borrow_content().status == ProcessingStatus::Failure)
+    }
+}
+
+/// 调用EACH模式的监听器, 状态为Failure的Item视为处理失败
+fn notify_item_processed(p: &SourceProcessor, ctx: &ListenerContext, source_item: &SourceItem) {
+    let Some(listeners) = p.options.process_listeners.get(&ListenerMode::Each) else {
+        return;
+    };
+    for listener in listeners {
+        invoke_item_listener(listener.as_ref(), ctx, source_item);
+    }
+}
+
+fn notify_item_error(
+    p: &SourceProcessor,
+    ctx: &ListenerContext,
+    source_item: &SourceItem,
+    err: &ProcessingError,
+) {
+    let Some(listeners) = p.options.process_listeners.get(&ListenerMode::Each) else {
+        return;
+    };
+    for listener in listeners {
+        invoke_listener(listener.as_ref(), || {
+            listener.on_item_error(ctx, source_item, err)
+        });
+    }
+}
+
+/// 调用BATCH模式监听器缓存的Item事件, 然后通知所有监听器处理完成
+fn notify_process_completed(p: &SourceProcessor, ctx: &ListenerContext) {
+    let listeners = &p.options.process_listeners;
+    if listeners.is_empty() {
+        return;
+    }
+    if let Some(batch_listeners) = listeners.get(&ListenerMode::Batch) {
+        for listener in batch_listeners {
+            for item in &ctx.processed_items {
+                invoke_item_listener(listener.as_ref(), ctx, item);
+            }
+            for (item, err) in &ctx.errors {
+                invoke_listener(listener.as_ref(), || listener.on_item_error(ctx, item, err));
+            }
+        }
+    }
+    for listener in listeners.values().flatten() {
+        invoke_listener(listener.as_ref(), || listener.on_process_completed(ctx));
+    }
+}
+
+fn invoke_item_listener(
+    listener: &dyn ProcessListener,
+    ctx: &ListenerContext,
+    source_item: &SourceItem,
+) {
+    let Some(item) = ctx.item_contents.get(&source_item.hashing()) else {
+        return;
+    };
+    let (content, files) = (item.borrow_content(), item.borrow_files());
+    match content.status {
+        ProcessingStatus::Renamed | ProcessingStatus::WaitingToRename => {
+            let item_content = ItemContent {
+                source_item,
+                file_contents: files,
+                item_variables: &content.item_content.item_variables,
+                status: content.status,
+            };
+            invoke_listener(listener, || listener.on_item_success(ctx, &item_content));
+        }
+        ProcessingStatus::Failure => {
+            let err =
+                ProcessingError::non_retryable(content.failure_reason.clone().unwrap_or_default());
+            invoke_listener(listener, || listener.on_item_error(ctx, source_item, &err));
+        }
+        _ => {}
+    }
+}
+
+/// 监听器的异常不影响处理流程, 需要panic策略为unwind
+fn invoke_listener(listener: &dyn ProcessListener, f: impl FnOnce()) {
+    if let Err(e) = std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        let message = e
+            .downcast_ref::<&str>()
+            .map(|x| x.to_string())
+            .or_else(|| e.downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        error!("[listener-error] {} cause={}", listener, message);
+    }
+}
+
+fn to_in_processing_item<'a>(
+    content: &'a ProcessingContent,
+    files: &'a Vec<FileContent>,
+    failure_reason: &'a Option<&'a str>,
+) -> InProcessingItem<'a> {
+    InProcessingItem {
+        id: &content.id,
+        processor_name: &content.processor_name,
+        item_hash: &content.item_hash,
+        item_identity: &content.item_identity,
+        source_item: &content.item_content.source_item,
+        item_variables: &content.item_content.item_variables,
+        file_contents: files,
+        rename_times: &content.rename_times,
+        status: &content.status,
+        failure_reason,
+    }
+}
+
+fn to_source_file(f: &FileContent, path: PathBuf) -> SourceFile {
+    SourceFile {
+        path,
+        attrs: f.attrs.clone(),
+        download_uri: f.file_uri.clone(),
+        tags: f.tags.clone(),
+        data: None,
+    }
+}
+
+pub fn encode_files_and_compress(files: &Vec<FileContent>) -> Result<Vec<u8>, ProcessingError> {
+    let bytes = if files.is_empty() {
+        vec![]
+    } else {
+        let bytes = postcard::to_stdvec(&files).map_err(|x| {
+            ProcessingError::non_retryable(format!("Failed to desc file content {}", x.to_string()))
+        })?;
+        // 压缩比待定
+        let level = 6;
+        zstd::encode_all(Cursor::new(bytes), level).map_err(|x| {
+            ProcessingError::non_retryable(format!(
+                "Failed to compress file content {}",
+                x.to_string()
+            ))
+        })?
+    };
+    Ok(bytes)
+}
+
+#[allow(dead_code)]
+pub fn decode_files_from_compressed(bytes: &[u8]) -> Result<Vec<FileContent>, ProcessingError> {
+    if bytes.is_empty() {
+        return Ok(vec![]);
+    }
+    let decompressed = zstd::decode_all(bytes).map_err(|x| {
+        ProcessingError::non_retryable(format!(
+            "Failed to decompress file content {}",
+            x.to_string()
+        ))
+    })?;
+    let files: Vec<FileContent> = postcard::from_bytes(&decompressed).map_err(|x| {
+        ProcessingError::non_retryable(format!(
+            "Failed to deserialize file content {}",
+            x.to_string()
+        ))
+    })?;
+    Ok(files)
+}
+
+#[derive(Debug)]
+pub struct DryRunOptions {
+    /// 为空时使用已保存的pointer
+    pub pointer: Option<Map<String, Value>>,
+    /// 是否过滤已处理过的Item
+    pub filter_processed: bool,
+}
+
+impl Default for DryRunOptions {
+    fn default() -> Self {
+        Self {
+            pointer: None,
+            filter_processed: true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunResult {
+    pub source_item: SourceItem,
+    pub item_variables: PatternVariables,
+    pub files: Vec<DryRunFileResult>,
+    pub status: ProcessingStatus,
+    pub filtered_by: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DryRunFileResult {
+    pub from: PathBuf,
+    pub target: PathBuf,
+    pub status: FileContentStatus,
+    pub errors: Vec<String>,
+}
+
+struct DryRunProcess {
+    options: DryRunOptions,
+    sender: tokio::sync::mpsc::Sender<DryRunResult>,
+    // Item并行处理完成的顺序不固定, 在on_item_success中按Source的顺序发送
+    pending: parking_lot::Mutex<HashMap<String, DryRunResult>>,
+}
+
+impl DryRunProcess {
+    async fn send_pending(&self, source_item: &SourceItem) {
+        let result = self.pending.lock().remove(&source_item.hashing());
+        if let Some(result) = result {
+            // 接收端已关闭, 由is_interrupted结束处理
+            let _ = self.sender.send(result).await;
+        }
+    }
+}
+
+impl Process for DryRunProcess {
+    fn select_item_filter<'a>(
+        &self,
+        p: &'a SourceProcessor,
+        item_strategy: Option<&'a ItemStrategy>,
+    ) -> Vec<&'a Arc<dyn SourceItemFilter>> {
+        item_strategy
+            .and_then(|x| x.item_filters.as_ref())
+            .unwrap_or(&p.options.item_filters)
+            .iter()
+            .filter(|x| {
+                self.options.filter_processed
+                    || !(x.as_ref() as &dyn Any).is::<SourceItemIdentityFilter>()
+            })
+            .collect()
+    }
+
+    fn rename_immediately(&self, _p: &SourceProcessor) -> bool {
+        false
+    }
+
+    fn is_interrupted(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    fn cancel_token(&self, _p: &SourceProcessor) -> CancellationToken {
+        CancellationToken::new()
+    }
+
+    async fn on_process_complete(
+        &self,
+        _p: &SourceProcessor,
+        _ctx: &ProcessRuntime,
+        _pointer: Arc<dyn SourcePointer>,
+    ) {
+    }
+
+    async fn on_item_process_complete(
+        &self,
+        _p: &SourceProcessor,
+        processing_content: &ProcessingContent,
+        files: &Vec<FileContent>,
+    ) -> Result<(), ProcessingError> {
+        let filtered_by = if processing_content.status == ProcessingStatus::Filtered {
+            processing_content.failure_reason.clone()
+        } else {
+            None
+        };
+        let files = files
+            .iter()
+            .map(|f| DryRunFileResult {
+                from: f.file_download_path.clone(),
+                target: f.target_path().to_path_buf(),
+                status: f.status,
+                errors: f.errors.clone(),
+            })
+            .collect_vec();
+        let result = DryRunResult {
+            source_item: processing_content.item_content.source_item.clone(),
+            item_variables: processing_content.item_content.item_variables.clone(),
+            files,
+            status: processing_content.status,
+            filtered_by,
+        };
+        self.pending
+            .lock()
+            .insert(processing_content.item_hash.clone(), result);
+        Ok(())
+    }
+
+    async fn on_item_success(
+        &self,
+        _p: &SourceProcessor,
+        _ctx: &ProcessRuntime,
+        source_item: &SourceItem,
+        _item_pointer: &Arc<dyn ItemPointer>,
+        _source_pointer: &Arc<dyn SourcePointer>,
+    ) {
+        self.send_pending(source_item).await;
+    }
+
+    async fn on_item_error(
+        &self,
+        _p: &SourceProcessor,
+        _ctx: &ProcessRuntime,
+        source_item: &SourceItem,
+        _err: &ProcessingError,
+    ) {
+        self.send_pending(source_item).await;
+    }
+
+    async fn on_item_skip(&self, _p: &SourceProcessor, source_item: &SourceItem, reason: &str) {
+        let result = DryRunResult {
+            source_item: source_item.clone(),
+            item_variables: PatternVariables::new(),
+            files: vec![],
+            status: ProcessingStatus::Filtered,
+            filtered_by: Some(reason.to_string()),
+        };
+        let _ = self.sender.send(result).await;
+    }
+
+    async fn get_source_pointer(
+        &self,
+        p: &SourceProcessor,
+        source_state: &ProcessorSourceState,
+    ) -> Result<Arc<dyn SourcePointer>, ProcessingError> {
+        let raw_pointer = match &self.options.pointer {
+            Some(pointer) => Value::Object(pointer.clone()),
+            None => source_state.last_pointer.to_owned(),
+        };
+        Ok(p.source.parse_raw_pointer(raw_pointer))
+    }
+
+    async fn do_download(
+        &self,
+        _p: &SourceProcessor,
+        _source_item: &SourceItem,
+        _file_contents: &[FileContent],
+    ) -> Result<(), ProcessingError> {
+        Ok(())
+    }
+
+    async fn preoccupy_target_paths(
+        &self,
+        _p: &SourceProcessor,
+        _source_item: &SourceItem,
+        _file_contents: &[FileContent],
+    ) -> Result<Vec<String>, ProcessingError> {
+        Ok(vec![])
+    }
+
+    async fn cancel_submitted_items(&self, _p: &SourceProcessor, _rt: &ProcessRuntime) {}
+}
+
+struct Reprocess {
+    content: ProcessingContent,
+    files: Vec<FileContent>,
+}
+
+impl Process for Reprocess {
+    fn select_item_filter<'a>(
+        &self,
+        _p: &'a SourceProcessor,
+        _item_strategy: Option<&'a ItemStrategy>,
+    ) -> Vec<&'a Arc<dyn SourceItemFilter>> {
+        vec![]
+    }
+
+    fn rename_immediately(&self, p: &SourceProcessor) -> bool {
+        self.content.status == ProcessingStatus::Renamed || !p.is_async_downloader()
+    }
+
+    fn detect_exists_targets(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        file_contents: &Vec<FileContent>,
+    ) -> HashMap<PathBuf, PathBuf> {
+        // 之前重命名的文件不算作已存在
+        let before_targets: HashSet<&PathBuf> = self
+            .files
+            .iter()
+            .filter(|f| matches!(f.status, Normal | Replace))
+            .map(|f| f.target_path())
+            .collect();
+        let mut mapping = detect_exists_targets(p, source_item, file_contents);
+        mapping.retain(|_, exists_path| !before_targets.contains(exists_path));
+        mapping
+    }
+
+    async fn on_process_complete(
+        &self,
+        _p: &SourceProcessor,
+        _ctx: &ProcessRuntime,
+        _pointer: Arc<dyn SourcePointer>,
+    ) {
+    }
+
+    async fn on_item_process_complete(
+        &self,
+        p: &SourceProcessor,
+        processing_content: &ProcessingContent,
+        files: &Vec<FileContent>,
+    ) -> Result<(), ProcessingError> {
+        let content = ProcessingContent {
+            id: self.content.id,
+            created_at: self.content.created_at,
+            updated_at: Some(OffsetDateTime::now_utc()),
+            ..processing_content.clone()
+        };
+        save_item_content(p, &content, files).await?;
+        if let Some(content_id) = content.id
+            && content.status == ProcessingStatus::Renamed
+        {
+            // 重新移动后之前的目标路径不再属于该content
+            let current_targets: HashSet<&PathBuf> = files
+                .iter()
+                .filter(|f| matches!(f.status, Normal | Replace))
+                .map(|f| f.target_path())
+                .collect();
+            let stale_targets = self
+                .files
+                .iter()
+                .filter(|f| matches!(f.status, Normal | Replace))
+                .map(|f| f.target_path())
+                .filter(|x| !current_targets.contains(x))
+                .map(|x| x.to_string_lossy().to_string())
+                .collect_vec();
+            p.processing_storage
+                .delete_file_targets(content_id, &stale_targets)
+                .await
+                .map_err(|x| {
+                    ProcessingError::non_retryable(format!(
+                        "Failed to delete file targets {}",
+                        x.message
+                    ))
+                })?;
+        }
+        info!(
+            "[reprocess-done] {} status:{:?}",
+            content.item_content.source_item, content.status
+        );
+        Ok(())
+    }
+
+    async fn do_download(
+        &self,
+        _p: &SourceProcessor,
+        _source_item: &SourceItem,
+        _file_contents: &[FileContent],
+    ) -> Result<(), ProcessingError> {
+        Ok(())
+    }
+
+    async fn do_movement(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        item_variables: &PatternVariables,
+        file_contents: &mut Vec<FileContent>,
+    ) -> Result<(), ProcessingError> {
+        if self.content.status != ProcessingStatus::Renamed {
+            return move_files(p, source_item, item_variables, file_contents).await;
+        }
+        self.relocate_files(p, source_item, file_contents).await
+    }
+}
+
+impl Reprocess {
+    /// 已重命名的文件从之前的目标路径移动到新的目标路径, 任意文件失败时回滚
+    async fn relocate_files(
+        &self,
+        p: &SourceProcessor,
+        source_item: &SourceItem,
+        file_contents: &mut [FileContent],
+    ) -> Result<(), ProcessingError> {
+        let before_targets: HashMap<&PathBuf, &PathBuf> = self
+            .files
+            .iter()
+            .filter(|f| matches!(f.status, Normal | Replace))
+            .map(|f| (&f.file_download_path, f.target_path()))
+            .collect();
+        // 源文件被保留或之前没有目标文件时从下载路径转移, 之前的目标文件在全部成功后删除
+        let retained = p.file_mover.is_source_retained();
+        let mut moved: Vec<(usize, Option<&PathBuf>)> = vec![];
+        let mut failure: Option<(usize, String)> = None;
+        for (idx, f) in file_contents.iter().enumerate() {
+            if f.status != Normal {
+                continue;
+            }
+            let before = before_targets.get(&f.file_download_path).copied();
+            if before == Some(f.target_path()) {
+                continue;
+            }
+            let from = match before {
+                Some(before) if !retained => before,
+                _ => &f.file_download_path,
+            };
+            let source_file = to_source_file(f, from.to_path_buf());
+            let target = f.target_path().to_string_lossy();
+            let result = match p
+                .file_mover
+                .create_directories(&f.target_save_path.to_string_lossy())
+            {
+                Ok(_) => {
+                    SourceProcessor::apply_retry(
+                        || async { p.file_mover.move_file(&source_file, &target) },
+                        "relocate-file",
+                        &p.options.retry.movement,
+                        &p.cancel_token(),
+                    )
+                    .await
+                }
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(_) => {
+                    info!(
+                        "[file-relocated] {} -> {}",
+                        from.display(),
+                        f.target_path().display()
+                    );
+                    moved.push((idx, before));
+                }
+                Err(e) => {
+                    failure = Some((idx, e.message().to_string()));
+                    break;
+                }
+            }
+        }
+        let Some((failed_idx, message)) = failure else {
+            if retained {
+                for before in moved.iter().filter_map(|(_, before)| *before) {
+                    if let Err(e) = p.file_mover.delete_file(&before.to_string_lossy()) {
+                        error!(
+                            "[file-relocated] Failed to delete {} cause={}",
+                            before.display(),
+                            e.message()
+                        );
+                    }
+                }
+            }
+            return Ok(());
+        };
+
+        for (idx, before) in moved.iter().rev() {
+            let f = &file_contents[*idx];
+            let origin = before.unwrap_or(&f.file_download_path);
+            if let Err(e) = undo_move_file(p, f, f.target_path(), origin) {
+                error!(
+                    "[movement-rollback] Failed to rollback {} -> {} cause={}",
+                    f.target_path().display(),
+                    origin.display(),
+                    e.message()
+                );
+            }
+        }
+        let f = &mut file_contents[failed_idx];
+        f.errors.push(message.clone());
+        let reason = format!("{}: {}", f.file_download_path.display(), message);
+        warn!("[movement-failed] {} {}", source_item, reason);
+        Err(ProcessingError::non_retryable(format!(
+            "Failed to move files {}",
+            reason
+        )))
+    }
+}
+struct FixedItemProcess {
+    items: Vec<SourceItem>,
+}
+
+impl Process for FixedItemProcess {
+    fn trigger_source(&self) -> Option<&str> {
+        Some("items")
+    }
+
+    async fn on_process_complete(
+        &self,
+        p: &SourceProcessor,
+        ctx: &ProcessRuntime,
+        _pointer: Arc<dyn SourcePointer>,
+    ) {
+        notify_process_completed(p, &ctx.listener_context.lock());
+    }
+
+    async fn on_item_error(
+        &self,
+        p: &SourceProcessor,
+        ctx: &ProcessRuntime,
+        source_item: &SourceItem,
+        err: &ProcessingError,
+    ) {
+        notify_item_error(p, &ctx.listener_context.lock(), source_item, err);
+    }
+
+    async fn on_item_success(
+        &self,
+        p: &SourceProcessor,
+        ctx: &ProcessRuntime,
+        source_item: &SourceItem,
+        _item_pointer: &Arc<dyn ItemPointer>,
+        _source_pointer: &Arc<dyn SourcePointer>,
+    ) {
+        notify_item_processed(p, &ctx.listener_context.lock(), source_item);
+    }
+
+    async fn on_item_process_complete(
+        &self,
+        p: &SourceProcessor,
+        processing_content: &ProcessingContent,
+        files: &Vec<FileContent>,
+    ) -> Result<(), ProcessingError> {
+        if !p.options.save_processing_content {
+            return Ok(());
+        }
+        save_item_content(p, processing_content, files).await
+    }
+
+    async fn fetch_items(
+        &self,
+        _p: &SourceProcessor,
+        _source_pointer: &Arc<dyn SourcePointer>,
+    ) -> Result<Vec<PointedItem>, ProcessingError> {
+        Ok(self
+            .items
+            .iter()
+            .map(|item| PointedItem {
+                source_item: item.clone(),
+                item_pointer: EMPTY_POINTER.clone(),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DryRunOptions;
+    use crate::config::ConfigOperator;
+    use crate::processor_test_support::test_support::*;
+    use itertools::Itertools;
+    use jsonpath_rust::JsonPath;
+    use serde_json::{Value, json};
+    use source_downloader_sdk::SourceItem;
+    use source_downloader_sdk::component::{FileContent, FileContentStatus, ProcessTask};
+    use source_downloader_sdk::storage::{
+        ProcessingStatus, ProcessingStorage, ProcessorRunQuery, RunOutcome,
+    };
+    use source_downloader_sdk::time::OffsetDateTime;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::Ordering;
+    use std::time::{Duration, Instant};
+
+    // <editor-fold desc="Sync item content tests">
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn sync_downloader_case() {
+        let cfg = cfg();
+        let pm = processor_manager().await;
+        let storage = storage().await;
+        for (name, case) in CASES.iter() {
+            pm.create_processor(
+                &cfg.get_processor_config(name)
+                    .expect("Failed to get processor config"),
+            );
+            let p = assert_processor(name, pm);
+            let root_path = V_PATH
+                .join(format!("/{}", name))
+                .expect("Failed to join path");
+            apply_case_files(&root_path, &case.files);
+
+            let result = p.run().await;
+            assert!(result.is_ok());
+
+            let content = build_result_json(storage, name).await;
+            for (assert_idx, assertion) in case.assertions.iter().enumerate() {
+                let selection = content.query(&assertion.select).unwrap_or_default();
+                if !assertion.allow_empty && selection.is_empty() {
+                    let err = AssertionError::new("Selection result is empty".to_string())
+                        .with_context(format!("case: {}", name))
+                        .with_context(format!("assertion #{}", assert_idx))
+                        .with_context(format!("select: {}", assertion.select));
+                    panic!("{}", err)
+                }
+                for (node_idx, node) in selection.iter().enumerate() {
+                    if let Err(err) = apply_assertion(node, &assertion.asserts) {
+                        let err = err
+                            .with_context(format!("case: {}", name))
+                            .with_context(format!("assertion #{}", assert_idx))
+                            .with_context(format!("select: {}", assertion.select))
+                            .with_context(format!("node index: {}", node_idx))
+                            .with_context(format!("content #{}", node));
+                        panic!("{}", err);
+                    }
+                }
+            }
+        }
+    }
+    // </editor-fold>
+
+    // <editor-fold desc="Flow control tests">
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_retry_then_ok() {
+        let name = "flow_ctr_retry_then_ok";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let r = p.run().await;
+        assert!(r.is_ok());
+        assert!(logs_contain("Retrying fetch-source-items delay"));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_movement_rollback() {
+        let name = "flow_ctr_movement_rollback";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let r = p.run().await;
+        assert!(r.is_ok());
+        assert!(logs_contain("[movement-rollback]"));
+
+        let content = build_result_json(storage().await, name).await;
+        assert_eq!(content[0]["status"], "Failure");
+        assert!(
+            content[0]["failure_reason"]
+                .as_str()
+                .unwrap()
+                .contains("multiple2: Mock move failed")
+        );
+        assert_eq!(content[0]["files"][1]["errors"][0], "Mock move failed");
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_batch_move() {
+        let name = "flow_ctr_batch_move";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        assert!(!logs_contain("Mock batch move failed"));
+
+        let content = build_result_json(storage().await, name).await;
+        assert_eq!(content[0]["status"], "Renamed");
+        assert_eq!(content[0]["files"][0]["status"], "Normal");
+        assert_eq!(content[0]["files"][1]["status"], "TargetExists");
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_rename_task() {
+        let name = "flow_ctr_rename_task";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        assert!(
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .all(|x| x["status"] == "WaitingToRename")
+        );
+
+        assert!(p.run_rename().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let item = |title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .cloned()
+                .unwrap()
+        };
+        assert_eq!(item("a")["status"], "Renamed");
+        assert_eq!(item("a")["rename_times"], 1);
+        assert_eq!(item("b")["status"], "WaitingToRename");
+        assert_eq!(item("b")["rename_times"], 1);
+        assert_eq!(item("c")["status"], "WaitingToRename");
+        assert_eq!(item("c")["rename_times"], 0);
+        for label in ["each", "batch"] {
+            assert!(logs_contain(&format!(
+                "[listener-item-success] {} a Renamed",
+                label
+            )));
+            assert!(logs_contain(&format!(
+                "[listener-completed] {} processed=1 has_error=false",
+                label
+            )));
+        }
+
+        assert!(p.run_rename().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let b = content
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|x| x["item_content"]["source_item"]["title"] == "b")
+            .unwrap();
+        assert_eq!(b["status"], "DownloadFailed");
+        assert_eq!(b["rename_times"], 2);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_dry_run() {
+        let name = "flow_ctr_dry_run";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let storage = storage().await;
+
+        let results = p.dry_run(DryRunOptions::default()).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].status, ProcessingStatus::WaitingToRename);
+        assert_eq!(results[0].files[0].status, FileContentStatus::Normal);
+        assert!(results[0].files[0].target.starts_with("test"));
+        assert_eq!(results[1].status, ProcessingStatus::Filtered);
+        assert_eq!(
+            results[1].filtered_by.as_deref(),
+            Some("Filtered by: expression")
+        );
+        assert!(!logs_contain("[movement-rollback]"));
+        assert_eq!(build_result_json(storage, name).await, json!([]));
+        let state = storage
+            .find_processor_source_state(name, &p.source_id)
+            .await
+            .unwrap();
+        assert!(state.is_none());
+        assert!(p.run_state.read().last_start_process_time.is_none());
+
+        assert!(p.run().await.is_ok());
+        let results = p.dry_run(DryRunOptions::default()).await.unwrap();
+        assert_eq!(results[0].status, ProcessingStatus::Filtered);
+        let results = p
+            .dry_run(DryRunOptions {
+                filter_processed: false,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        assert_eq!(results[0].status, ProcessingStatus::WaitingToRename);
+
+        // 不占用运行状态, 也不会被取消
+        let end_time = p.run_state.read().last_end_process_time;
+        p.processing.store(true, Ordering::Release);
+        assert!(p.cancel(false));
+        let results = p.dry_run(DryRunOptions::default()).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(p.processing.load(Ordering::Acquire));
+        assert_eq!(p.run_state.read().last_end_process_time, end_time);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_dry_run_interrupted() {
+        let name = "flow_ctr_dry_run";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let (sender, receiver) = tokio::sync::mpsc::channel(1);
+        drop(receiver);
+        let r = p.dry_run_stream(DryRunOptions::default(), sender).await;
+        assert!(r.is_ok());
+        assert!(logs_contain("[run-interrupted]"));
+        assert!(!logs_contain("[item-start]"));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_reprocess() {
+        let name = "flow_ctr_reprocess";
+        let mut cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let before = build_result_json(storage().await, name).await;
+        assert_eq!(before[0]["status"], "Renamed");
+
+        cfg.save_path = "test-reprocess".to_string();
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let id = before[0]["id"].as_i64().unwrap();
+        assert!(p.reprocess(id).await.is_ok());
+        assert!(logs_contain("[file-relocated]"));
+
+        let after = build_result_json(storage().await, name).await;
+        assert_eq!(after.as_array().unwrap().len(), 1);
+        assert_eq!(after[0]["id"], id);
+        assert_eq!(after[0]["status"], "Renamed");
+        assert_eq!(after[0]["created_at"], before[0]["created_at"]);
+        assert!(
+            after[0]["files"][0]["target_save_path"]
+                .as_str()
+                .unwrap()
+                .starts_with("test-reprocess")
+        );
+        let target_path = |content: &Value| {
+            let file = &content[0]["files"][0];
+            PathBuf::from(file["target_save_path"].as_str().unwrap())
+                .join(file["target_filename"].as_str().unwrap())
+                .to_string_lossy()
+                .to_string()
+        };
+        let storage = storage().await;
+        assert!(
+            storage
+                .find_content_by_target_path(&target_path(&before))
+                .await
+                .unwrap()
+                .is_none()
+        );
+        let owner = storage
+            .find_content_by_target_path(&target_path(&after))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(owner.id, Some(id));
+        assert!(p.reprocess(i64::MAX).await.is_err());
+
+        p.processing.store(true, Ordering::Release);
+        let err = p.reprocess(id).await.unwrap_err();
+        assert_eq!(err.message(), "Already processing");
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_run_items() {
+        let name = "flow_ctr_run_items";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let storage = storage().await;
+        let item = SourceItem {
+            title: "fixed".to_string(),
+            link: "file://flow_ctr_run_items/fixed".parse().unwrap(),
+            datetime: OffsetDateTime::now_utc(),
+            content_type: "text".to_string(),
+            download_uri: "file://flow_ctr_run_items/fixed".parse().unwrap(),
+            attrs: Default::default(),
+            tags: Default::default(),
+            identity: None,
+        };
+        assert!(p.run_items(vec![item]).await.is_ok());
+        let content = build_result_json(storage, name).await;
+        assert_eq!(content[0]["status"], "Renamed");
+        assert_eq!(content[0]["item_content"]["source_item"]["title"], "fixed");
+        let state = storage
+            .find_processor_source_state(name, &p.source_id)
+            .await
+            .unwrap();
+        assert!(state.is_none());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_listener() {
+        let name = "flow_ctr_listener";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        for label in ["each", "batch"] {
+            assert!(logs_contain(&format!(
+                "[listener-item-success] {} a",
+                label
+            )));
+            assert!(logs_contain(&format!(
+                "[listener-item-error] {} fail reason=Some(",
+                label
+            )));
+            assert!(logs_contain(&format!(
+                "[listener-completed] {} processed=3 has_error=true",
+                label
+            )));
+        }
+        assert!(logs_contain(
+            "[listener-error] recording:each cause=Mock listener failed"
+        ));
+        assert!(logs_contain(
+            "[listener-error] recording:batch cause=Mock listener failed"
+        ));
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_parallelism() {
+        let name = "flow_ctr_parallelism";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let titles = (1..=6).map(|i| format!("p{}", i)).collect::<Vec<_>>();
+        let results = p.dry_run(DryRunOptions::default()).await.unwrap();
+        let result_titles = results
+            .iter()
+            .map(|x| x.source_item.title.clone())
+            .collect::<Vec<_>>();
+        assert_eq!(result_titles, titles);
+
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let contents = content.as_array().unwrap();
+        assert_eq!(contents.len(), 6);
+        assert!(contents.iter().all(|x| x["status"] == "Renamed"));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_item_error() {
+        let name = "flow_ctr_item_error";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let targets = p
+            .dry_run(DryRunOptions::default())
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|x| x.source_item.title == "submit-error")
+            .flat_map(|x| x.files)
+            .map(|x| x.target.to_string_lossy().to_string())
+            .collect_vec();
+        assert!(!targets.is_empty());
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let status = |title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .map(|x| x["status"].clone())
+        };
+        // 提交失败后释放预占用的目标路径
+        let preoccupied = storage().await.find_paths(&targets).await;
+        assert!(preoccupied.unwrap().is_empty());
+        assert_eq!(status("a").unwrap(), "Renamed");
+        assert_eq!(status("submit-skip").unwrap(), "Failure");
+        // 不可跳过的异常结束本次处理, 且不保存失败记录
+        assert!(status("submit-error").is_none());
+        assert!(status("b").is_none());
+        let failure = content
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|x| x["item_content"]["source_item"]["title"] == "submit-skip")
+            .unwrap();
+        assert_eq!(failure["failure_reason"], "Mock submit skipped");
+
+        // pointer停留在失败的Item之前, 下次触发时重新处理
+        assert!(p.run().await.is_ok());
+        logs_assert(|lines: &[&str]| {
+            let count = lines
+                .iter()
+                .filter(|x| x.contains("[item-error]") && x.contains("title: \"submit-error\""))
+                .count();
+            match count {
+                2 => Ok(()),
+                n => Err(format!("submit-error processed {} times", n)),
+            }
+        });
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_item_timeout() {
+        let name = "flow_ctr_item_timeout";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let start = Instant::now();
+        assert!(p.run().await.is_ok());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        let content = build_result_json(storage().await, name).await;
+        let find = |title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .cloned()
+        };
+        assert_eq!(find("a").unwrap()["status"], "Renamed");
+        assert!(logs_contain(
+            "Item processing timed out after 100ms at stage resolve-files"
+        ));
+        // 超时为可重试的异常, 没有开启item-error-continue时结束本次处理, 下次触发时重新处理
+        assert!(find("resolve-hang").is_none());
+        assert!(find("c").is_none());
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_events() {
+        let name = "flow_ctr_events";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        let mut receiver = pm.event_bus().subscribe();
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+
+        let mut events = vec![];
+        while let Ok(event) = receiver.try_recv() {
+            if event.processor_name == name {
+                events.push(serde_json::to_value(&event).unwrap());
+            }
+        }
+        let kinds = events
+            .iter()
+            .map(|x| {
+                format!(
+                    "{}:{}",
+                    x["type"].as_str().unwrap(),
+                    x["item"]["title"].as_str().unwrap_or_default()
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            kinds,
+            vec![
+                "run-started:",
+                "item-submitted:a",
+                "item-renamed:a",
+                "item-filtered:b",
+                "item-failed:submit-error",
+                "run-finished:",
+            ]
+        );
+        assert_eq!(events[0]["trigger_source"], "trigger");
+        assert!(!events[3]["filter"].as_str().unwrap().is_empty());
+        assert_eq!(events[4]["message"], "Mock submit failed");
+        assert_eq!(events[5]["processed_count"], 2);
+        assert!(
+            events
+                .iter()
+                .all(|x| x["trace_id"] == events[0]["trace_id"])
+        );
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_item_error_continue() {
+        let name = "flow_ctr_item_error_continue";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let status = |title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .map(|x| x["status"].clone())
+        };
+        assert_eq!(status("submit-error").unwrap(), "Failure");
+        assert_eq!(status("b").unwrap(), "Renamed");
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_item_error_drain() {
+        let name = "flow_ctr_item_error_drain";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let status = |title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .map(|x| x["status"].clone())
+        };
+        assert_eq!(status("a").unwrap(), "Renamed");
+        assert!(status("submit-error").is_none());
+        // 异常时已经在处理中的Item仍然处理完成, 之后的Item不再处理
+        assert_eq!(status("submit-slow").unwrap(), "Renamed");
+        assert!(status("d").is_none());
+        assert!(logs_contain("[item-non-retryable-error]"));
+        // pointer停留在失败的Item之前
+        let state = storage()
+            .await
+            .find_processor_source_state(name, &p.source_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.last_pointer, json!({"last": "a"}));
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_target_exists() {
+        let name = "flow_ctr_target_exists";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let find = |title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .cloned()
+                .unwrap()
+        };
+        let exists = find("exists");
+        assert_eq!(exists["status"], "TargetAlreadyExists");
+        assert_eq!(exists["files"][0]["status"], "TargetExists");
+
+        let multiple = find("multiple");
+        assert_eq!(multiple["status"], "Renamed");
+        assert_eq!(multiple["files"][0]["status"], "Normal");
+        assert_eq!(multiple["files"][1]["status"], "TargetExists");
+        assert!(
+            multiple["files"][1]["exist_target_path"]
+                .as_str()
+                .unwrap()
+                .ends_with("multiple2")
+        );
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_replace() {
+        let name = "flow_ctr_replace";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        assert_eq!(content[0]["status"], "Renamed");
+        assert_eq!(content[0]["files"][0]["status"], "Replace");
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_replace_before() {
+        let name = "flow_ctr_replace_before";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let contents = content.as_array().unwrap();
+        assert_eq!(contents.len(), 2);
+        assert!(contents.iter().all(|x| x["status"] == "Renamed"));
+        let file_status = |title: &str| {
+            contents
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .map(|x| x["files"][0]["status"].clone())
+                .unwrap()
+        };
+        // 被替换的Item的文件标记为已替换
+        assert_eq!(file_status("first"), "Replaced");
+        assert_eq!(file_status("second"), "Replace");
+
+        // 目标路径由替换后的Item接管
+        let file = &contents[0]["files"][0];
+        let target = std::path::PathBuf::from(file["target_save_path"].as_str().unwrap())
+            .join(file["target_filename"].as_str().unwrap())
+            .to_string_lossy()
+            .to_string();
+        let owner = storage()
+            .await
+            .find_content_by_target_path(&target)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(owner.item_content.source_item.title, "second");
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_preoccupied() {
+        let name = "flow_ctr_preoccupied";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let status = |content: &serde_json::Value, title: &str| {
+            content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == title)
+                .map(|x| x["status"].clone())
+                .unwrap()
+        };
+        assert_eq!(status(&content, "x"), "WaitingToRename");
+        assert_eq!(status(&content, "y"), "TargetAlreadyExists");
+        let file = &content[0]["files"][0];
+        let target = std::path::PathBuf::from(file["target_save_path"].as_str().unwrap())
+            .join(file["target_filename"].as_str().unwrap())
+            .to_string_lossy()
+            .to_string();
+        let preoccupied = storage()
+            .await
+            .find_paths(std::slice::from_ref(&target))
+            .await;
+        assert_eq!(preoccupied.unwrap().len(), 1);
+
+        assert!(p.run_rename().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        assert_eq!(status(&content, "x"), "Renamed");
+        let preoccupied = storage().await.find_paths(&[target]).await;
+        assert!(preoccupied.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_cancel() {
+        let name = "flow_ctr_cancel";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert!(!p.cancel(true));
+        // 等待submit-slow开始处理后再取消
+        let cancel = async {
+            while !logs_contain("[item-start] SourceItem { title: \"submit-slow\"") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            p.cancel(true)
+        };
+        let (result, cancelled) = tokio::join!(p.run(), cancel);
+        assert!(result.is_ok());
+        assert!(cancelled);
+        assert!(logs_contain("[run-cancelled]"));
+
+        let content = build_result_json(storage().await, name).await;
+        let titles = content
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["item_content"]["source_item"]["title"].as_str().unwrap())
+            .collect_vec();
+        // 提交中被中断的Item不保存, 下次触发时重新处理
+        assert_eq!(titles, vec!["a"]);
+        assert_eq!(content[0]["status"], "Cancelled");
+        // 执行中的提交被中断, pointer停留在最后完成的Item
+        assert!(logs_contain("submit-download cancelled"));
+        let state = storage()
+            .await
+            .find_processor_source_state(name, &p.source_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.last_pointer, json!({"last": "a"}));
+        // 下一次运行不受影响
+        assert!(!p.cancel(false));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_cancel_continue() {
+        let name = "flow_ctr_cancel_continue";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        // 等待submit-slow开始处理后再取消
+        let cancel = async {
+            while !logs_contain("[item-start] SourceItem { title: \"submit-slow\"") {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            p.cancel(false)
+        };
+        let (result, cancelled) = tokio::join!(p.run(), cancel);
+        assert!(result.is_ok());
+        assert!(cancelled);
+        assert!(logs_contain("[item-cancelled]"));
+        // 取消不是Item的异常, 即使开启了item-error-continue也不保存失败记录
+        assert_eq!(build_result_json(storage().await, name).await, json!([]));
+
+        assert!(p.run().await.is_ok());
+        let content = build_result_json(storage().await, name).await;
+        let titles = content
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|x| x["status"] == "Renamed")
+            .map(|x| x["item_content"]["source_item"]["title"].as_str().unwrap())
+            .sorted()
+            .collect_vec();
+        assert_eq!(titles, vec!["b", "submit-slow"]);
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_retained_source() {
+        let name = "flow_ctr_retained_source";
+        for mover in ["system-file:copy", "system-file:hardlink"] {
+            let dir = tempfile::tempdir().unwrap();
+            let mut cfg = cfg()
+                .get_processor_config(name)
+                .expect("Failed to get processor config");
+            cfg.file_mover = mover.to_string();
+            cfg.save_path = dir.path().join("before").to_string_lossy().to_string();
+            let pm = processor_manager().await;
+            pm.create_processor(&cfg);
+            let p = assert_processor(name, pm);
+            let source = dir.path().join("a.txt");
+            std::fs::write(&source, "a").unwrap();
+            let uri = format!("file://localhost{}", source.display());
+            let item = SourceItem {
+                title: mover.to_string(),
+                link: uri.parse().unwrap(),
+                datetime: OffsetDateTime::now_utc(),
+                content_type: "text".to_string(),
+                download_uri: uri.parse().unwrap(),
+                attrs: Default::default(),
+                tags: Default::default(),
+                identity: None,
+            };
+
+            // 回滚时删除已经转移的目标文件
+            let rollback_dir = dir.path().join("rollback");
+            let file_content = |path: &Path| {
+                serde_json::from_value::<FileContent>(json!({
+                    "download_path": dir.path(),
+                    "file_download_path": path,
+                    "source_save_path": dir.path(),
+                    "pattern_variables": {},
+                    "tags": [],
+                    "attrs": {},
+                    "file_uri": null,
+                    "target_save_path": rollback_dir,
+                    "target_filename": path.file_name().unwrap().to_string_lossy(),
+                    "exist_target_path": null,
+                    "errors": [],
+                    "status": "Normal",
+                }))
+                .unwrap()
+            };
+            let mut files = vec![
+                file_content(&source),
+                file_content(&dir.path().join("missing.txt")),
+            ];
+            let result = super::move_files(&p, &item, &Default::default(), &mut files).await;
+            assert!(result.is_err());
+            assert!(!rollback_dir.join("a.txt").exists());
+            assert!(source.exists());
+
+            // 重新处理时删除之前的目标文件
+            assert!(p.run_items(vec![item.clone()]).await.is_ok());
+            assert!(p.run_rename().await.is_ok());
+            let content = build_result_json(storage().await, name).await;
+            let content = content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["item_content"]["source_item"]["title"] == mover)
+                .cloned()
+                .unwrap();
+            assert_eq!(content["status"], "Renamed");
+            let target = |content: &Value| {
+                let file = &content["files"][0];
+                PathBuf::from(file["target_save_path"].as_str().unwrap())
+                    .join(file["target_filename"].as_str().unwrap())
+            };
+            let before = target(&content);
+            assert!(before.exists());
+
+            cfg.save_path = dir.path().join("after").to_string_lossy().to_string();
+            pm.create_processor(&cfg);
+            let p = assert_processor(name, pm);
+            let id = content["id"].as_i64().unwrap();
+            assert!(p.reprocess(id).await.is_ok());
+            let content = build_result_json(storage().await, name).await;
+            let content = content
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|x| x["id"] == id)
+                .cloned()
+                .unwrap();
+            let after = target(&content);
+            assert!(after.starts_with(dir.path().join("after")));
+            assert!(after.exists());
+            assert!(!before.exists());
+            assert!(source.exists());
+
+            // 之前没有目标文件时从下载路径转移
+            let process = super::Reprocess {
+                content: p
+                    .processing_storage
+                    .find_content_by_id(id)
+                    .await
+                    .unwrap()
+                    .unwrap(),
+                files: vec![],
+            };
+            let mut files = vec![file_content(&source)];
+            assert!(process.relocate_files(&p, &item, &mut files).await.is_ok());
+            assert!(rollback_dir.join("a.txt").exists());
+            assert!(source.exists());
+        }
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_cancel_retry() {
+        let name = "flow_ctr_cancel_retry";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let start = Instant::now();
+        let cancel = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            p.cancel(false)
+        };
+        let (result, cancelled) = tokio::join!(p.run(), cancel);
+        assert!(cancelled);
+        assert!(result.unwrap_err().contains("cancelled"));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_retry_policy() {
+        let name = "flow_ctr_retry_policy";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert_eq!(p.options.retry.fetch.max_attempts, 3);
+        assert_eq!(p.options.retry.movement.max_attempts, 1);
+        assert_eq!(p.options.retry.movement.max_delay, Duration::from_secs(1));
+        let start = Instant::now();
+        assert!(p.run().await.is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn flow_ctr_source_health() {
+        let name = "flow_ctr_source_health";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        assert_eq!(p.source_health().await.unwrap().consecutive_failures, 0);
+
+        assert!(ProcessTask::run(p.as_ref()).await.is_err());
+        assert!(ProcessTask::run(p.as_ref()).await.is_err());
+        let health = p.source_health().await.unwrap();
+        assert_eq!(health.consecutive_failures, 2);
+        assert!(health.is_cooling_down(OffsetDateTime::now_utc()));
+        let state = storage()
+            .await
+            .find_processor_source_state(name, &p.source_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.retry_times, 2);
+
+        // 冷却中跳过触发器的调度, 手动运行不受影响
+        assert!(ProcessTask::run(p.as_ref()).await.is_ok());
+        assert!(logs_contain("[source-cooldown]"));
+        assert!(p.run_manually().await.is_ok());
+        let health = p.source_health().await.unwrap();
+        assert_eq!(health.consecutive_failures, 0);
+        assert!(!health.is_cooling_down(OffsetDateTime::now_utc()));
+        let state = storage()
+            .await
+            .find_processor_source_state(name, &p.source_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(state.retry_times, 0);
+    }
+
+    #[tokio::test]
+    async fn flow_ctr_runtime_snapshot() {
+        let name = "flow_ctr_runtime_snapshot";
+        let cfg = cfg()
+            .get_processor_config(name)
+            .expect("Failed to get processor config");
+        let pm = processor_manager().await;
+        pm.create_processor(&cfg);
+        let p = assert_processor(name, pm);
+        let snapshot = p.runtime_snapshot();
+        assert!(snapshot.last_start_process_time.is_none());
+        assert!(!snapshot.processing);
+
+        assert!(p.run().await.is_err());
+        let snapshot = p.runtime_snapshot();
+        assert!(snapshot.last_start_process_time.unwrap() >= snapshot.created_at);
+        assert!(snapshot.last_end_process_time >= snapshot.last_start_process_time);
+        assert_eq!(
+            snapshot.last_process_failed_message.as_deref(),
+            Some("Mock non-retryable")
+        );
+        assert!(!snapshot.processing);
+
+        assert!(p.run().await.is_ok());
+        assert!(p.runtime_snapshot().last_process_failed_message.is_none());
+
+        assert!(p.run_manually().await.is_ok());
+        let runs = storage()
+            .await
+            .query_processor_runs(&ProcessorRunQuery {
+                processor_name: name.to_string(),
+                max_id: None,
+                limit: 10,
+            })
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[0].trigger_source, "manual");
+        assert_eq!(runs[1].trigger_source, "trigger");
+        assert_eq!(runs[1].outcome, RunOutcome::Success);
+        assert_eq!(runs[2].outcome, RunOutcome::Failure);
+        assert_eq!(runs[2].error.as_deref(), Some("Mock non-retryable"));
+        assert!(runs[2].fetch_took_ms.is_none());
     }
     // </editor-fold>
 }
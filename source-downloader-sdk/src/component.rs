@@ -377,8 +377,10 @@ pub trait Downloader: SdComponent {
     async fn cancel(&self, item: &SourceItem, files: &[SourceFile]) -> Result<(), ProcessingError>;
 }
 
+#[async_trait]
 pub trait AsyncDownloader: Downloader {
-    fn is_finished(&self, item: &SourceItem) -> Option<bool>;
+    /// `None` 表示下载器中找不到对应的任务
+    async fn is_finished(&self, item: &SourceItem) -> Option<bool>;
 }
 
 #[async_trait]
@@ -400,15 +402,13 @@ pub trait Source: SdComponent {
 
 #[async_trait]
 pub trait ItemFileResolver: SdComponent {
-    async fn resolve_files(&self, item: &SourceItem) -> Vec<SourceFile>;
+    async fn resolve_files(&self, item: &SourceItem) -> Result<Vec<SourceFile>, ProcessingError>;
 }
 
 pub trait FileMover: SdComponent {
-    fn move_file(
-        &self,
-        source_file: &SourceFile,
-        download_path: &str,
-    ) -> Result<(), ProcessingError>;
+    /// Move the file at `source_file.path` to `target_path`
+    fn move_file(&self, source_file: &SourceFile, target_path: &str)
+    -> Result<(), ProcessingError>;
     fn exists(&self, path: &Vec<&PathBuf>) -> Vec<bool>;
     fn create_directories(&self, path: &str) -> Result<(), ProcessingError>;
     fn replace(&self, item_content: &ItemContent) -> Result<(), ProcessingError>;
@@ -417,11 +417,22 @@ pub trait FileMover: SdComponent {
     fn is_supported_batch_move(&self) -> bool {
         false
     }
+    /// Move all files in `Normal` status of the item at once
     fn batch_move(&self, _: &ItemContent) -> Result<(), ProcessingError> {
         Err(ProcessingError::non_retryable(
             "Batch move is not supported",
         ))
     }
+    /// Whether the source file is kept after `move_file`, e.g. copy or link
+    fn is_source_retained(&self) -> bool {
+        false
+    }
+    /// Delete a file created by `move_file`, required when the source file is retained
+    fn delete_file(&self, _path: &str) -> Result<(), ProcessingError> {
+        Err(ProcessingError::non_retryable(
+            "Delete file is not supported",
+        ))
+    }
 }
 
 pub trait ProcessListener: SdComponent {
@@ -705,7 +716,7 @@ pub trait ProcessTask: Send + Sync {
     fn group(&self) -> Option<String>;
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct FileContent {
     /// /mnt/downloads
     pub download_path: PathBuf,
@@ -790,7 +801,7 @@ pub struct InProcessingItem<'a> {
     pub failure_reason: &'a Option<&'a str>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 pub enum FileContentStatus {
     Undetected,
 
@@ -27,6 +27,17 @@ pub trait ProcessingStorage: Send + Sync {
 
     async fn find_file_contents(&self, content_id: i64) -> Result<Option<Vec<u8>>, Error>;
 
+    /// Record the target paths of renamed files, a path owned by another content will be taken over.
+    async fn save_file_targets(&self, content_id: i64, paths: Vec<String>) -> Result<(), Error>;
+
+    /// Delete the target paths still owned by the content, paths taken over by others are kept.
+    async fn delete_file_targets(&self, content_id: i64, paths: &[String]) -> Result<(), Error>;
+
+    async fn find_content_by_target_path(
+        &self,
+        path: &str,
+    ) -> Result<Option<ProcessingContent>, Error>;
+
     async fn find_processor_source_state(
         &self,
         processor_name: &str,
@@ -38,7 +49,33 @@ pub trait ProcessingStorage: Send + Sync {
         state: &ProcessorSourceState,
     ) -> Result<ProcessorSourceState, Error>;
 
+    /// 预占用目标路径, 已存在的路径会被覆盖
     async fn save_paths(&self, paths: Vec<ProcessingTargetPath>) -> Result<(), Error>;
+
+    /// 原子地预占用目标路径, 返回被其他Item占用的路径, 存在冲突时不占用任何路径
+    async fn reserve_paths(
+        &self,
+        paths: Vec<ProcessingTargetPath>,
+    ) -> Result<Vec<ProcessingTargetPath>, Error>;
+
+    /// 返回已被预占用的路径
+    async fn find_paths(&self, paths: &[String]) -> Result<Vec<ProcessingTargetPath>, Error>;
+
+    async fn delete_paths(&self, paths: &[String]) -> Result<(), Error>;
+
+    async fn delete_paths_by_item(
+        &self,
+        processor_name: &str,
+        item_hash: &str,
+    ) -> Result<(), Error>;
+
+    async fn save_processor_run(&self, run: &ProcessorRun) -> Result<i64, Error>;
+
+    /// 按id倒序返回
+    async fn query_processor_runs(
+        &self,
+        query: &ProcessorRunQuery,
+    ) -> Result<Vec<ProcessorRun>, Error>;
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -127,6 +164,52 @@ pub struct ProcessorSourceState {
     pub processor_name: String,
     pub source_id: String,
     pub last_pointer: Value,
+    /// 连续获取失败的次数, 成功后重置
+    pub retry_times: u32,
+    pub last_active_at: Option<OffsetDateTime>,
+}
+
+/// 处理器的一次运行记录
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessorRun {
+    pub id: Option<i64>,
+    pub trace_id: String,
+    pub processor_name: String,
+    /// 触发来源, 例如 trigger, manual, items
+    pub trigger_source: String,
+    pub started_at: OffsetDateTime,
+    pub ended_at: OffsetDateTime,
+    pub fetched_count: u32,
+    pub processed_count: u32,
+    pub filtered_count: u32,
+    pub fetch_took_ms: Option<u64>,
+    pub outcome: RunOutcome,
+    pub error: Option<String>,
+}
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RunOutcome {
+    Success = 0,
+    Failure = 1,
+    Cancelled = 2,
+}
+
+impl From<i32> for RunOutcome {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => RunOutcome::Success,
+            2 => RunOutcome::Cancelled,
+            _ => RunOutcome::Failure,
+        }
+    }
+}
+
+pub struct ProcessorRunQuery {
+    pub processor_name: String,
+    /// 返回id小于max_id的记录
+    pub max_id: Option<i64>,
+    pub limit: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -1,7 +1,7 @@
 use async_trait::async_trait;
 use source_downloader_sdk::storage::{
     Error, ProcessingContent, ProcessingContentQuery, ProcessingStorage, ProcessingTargetPath,
-    ProcessorSourceState,
+    ProcessorRun, ProcessorRunQuery, ProcessorSourceState,
 };
 use std::collections::HashMap;
 use std::sync::RwLock;
@@ -10,20 +10,34 @@ use std::sync::RwLock;
 #[allow(dead_code)]
 pub struct MemoryProcessingStorage {
     contents: RwLock<HashMap<i64, ProcessingContent>>,
+    runs: RwLock<Vec<ProcessorRun>>,
+    paths: RwLock<HashMap<String, ProcessingTargetPath>>,
+    // 目标路径 -> content id
+    file_targets: RwLock<HashMap<String, i64>>,
 }
 
 impl MemoryProcessingStorage {
     pub fn new() -> Self {
         Self {
             contents: RwLock::new(HashMap::new()),
+            runs: RwLock::new(Vec::new()),
+            paths: RwLock::new(HashMap::new()),
+            file_targets: RwLock::new(HashMap::new()),
         }
     }
 }
 
 #[async_trait]
 impl ProcessingStorage for MemoryProcessingStorage {
-    async fn save_processing_content(&self, _: &ProcessingContent) -> Result<i64, Error> {
-        todo!()
+    async fn save_processing_content(&self, content: &ProcessingContent) -> Result<i64, Error> {
+        let mut contents = self.contents.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        let id = content.id.unwrap_or(contents.len() as i64 + 1);
+        let mut content = content.clone();
+        content.id = Some(id);
+        contents.insert(id, content);
+        Ok(id)
     }
 
     async fn processing_content_exists(&self, _: &str, _: &str) -> Result<bool, Error> {
@@ -42,8 +56,11 @@ impl ProcessingStorage for MemoryProcessingStorage {
         todo!()
     }
 
-    async fn find_content_by_id(&self, _: i64) -> Result<Option<ProcessingContent>, Error> {
-        todo!()
+    async fn find_content_by_id(&self, id: i64) -> Result<Option<ProcessingContent>, Error> {
+        let contents = self.contents.read().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        Ok(contents.get(&id).cloned())
     }
 
     async fn query_processing_content(
@@ -61,6 +78,44 @@ impl ProcessingStorage for MemoryProcessingStorage {
         todo!()
     }
 
+    async fn save_file_targets(&self, content_id: i64, paths: Vec<String>) -> Result<(), Error> {
+        let mut targets = self.file_targets.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        for path in paths {
+            targets.insert(path, content_id);
+        }
+        Ok(())
+    }
+
+    async fn delete_file_targets(&self, content_id: i64, paths: &[String]) -> Result<(), Error> {
+        let mut targets = self.file_targets.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        for path in paths {
+            if targets.get(path) == Some(&content_id) {
+                targets.remove(path);
+            }
+        }
+        Ok(())
+    }
+
+    async fn find_content_by_target_path(
+        &self,
+        path: &str,
+    ) -> Result<Option<ProcessingContent>, Error> {
+        let content_id = {
+            let targets = self.file_targets.read().map_err(|e| Error {
+                message: e.to_string(),
+            })?;
+            targets.get(path).copied()
+        };
+        match content_id {
+            Some(id) => self.find_content_by_id(id).await,
+            None => Ok(None),
+        }
+    }
+
     async fn find_processor_source_state(
         &self,
         _: &str,
@@ -76,10 +131,256 @@ impl ProcessingStorage for MemoryProcessingStorage {
         todo!()
     }
 
-    async fn save_paths(&self, _: Vec<ProcessingTargetPath>) -> Result<(), Error> {
-        todo!()
+    async fn save_paths(&self, paths: Vec<ProcessingTargetPath>) -> Result<(), Error> {
+        let mut saved = self.paths.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        for path in paths {
+            saved.insert(path.path.clone(), path);
+        }
+        Ok(())
+    }
+
+    async fn reserve_paths(
+        &self,
+        paths: Vec<ProcessingTargetPath>,
+    ) -> Result<Vec<ProcessingTargetPath>, Error> {
+        let mut saved = self.paths.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        let occupied = paths
+            .iter()
+            .filter_map(|path| saved.get(&path.path))
+            .filter(|x| {
+                paths.iter().any(|p| {
+                    p.path == x.path
+                        && (p.processor_name != x.processor_name || p.item_hash != x.item_hash)
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        if occupied.is_empty() {
+            for path in paths {
+                saved.insert(path.path.clone(), path);
+            }
+        }
+        Ok(occupied)
+    }
+
+    async fn find_paths(&self, paths: &[String]) -> Result<Vec<ProcessingTargetPath>, Error> {
+        let saved = self.paths.read().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        Ok(paths.iter().filter_map(|x| saved.get(x)).cloned().collect())
+    }
+
+    async fn delete_paths(&self, paths: &[String]) -> Result<(), Error> {
+        let mut saved = self.paths.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        for path in paths {
+            saved.remove(path);
+        }
+        Ok(())
+    }
+
+    async fn delete_paths_by_item(
+        &self,
+        processor_name: &str,
+        item_hash: &str,
+    ) -> Result<(), Error> {
+        let mut saved = self.paths.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        saved.retain(|_, x| x.processor_name != processor_name || x.item_hash != item_hash);
+        Ok(())
+    }
+
+    async fn save_processor_run(&self, run: &ProcessorRun) -> Result<i64, Error> {
+        let mut runs = self.runs.write().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        let id = run.id.unwrap_or(runs.len() as i64 + 1);
+        let mut run = run.clone();
+        run.id = Some(id);
+        match runs.iter_mut().find(|x| x.id == Some(id)) {
+            Some(exists) => *exists = run,
+            None => runs.push(run),
+        }
+        Ok(id)
+    }
+
+    async fn query_processor_runs(
+        &self,
+        query: &ProcessorRunQuery,
+    ) -> Result<Vec<ProcessorRun>, Error> {
+        let runs = self.runs.read().map_err(|e| Error {
+            message: e.to_string(),
+        })?;
+        Ok(runs
+            .iter()
+            .rev()
+            .filter(|x| x.processor_name == query.processor_name)
+            .filter(|x| {
+                query
+                    .max_id
+                    .is_none_or(|max_id| x.id.unwrap_or_default() < max_id)
+            })
+            .take(query.limit as usize)
+            .cloned()
+            .collect())
     }
 }
 
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use crate::MemoryProcessingStorage;
+    use source_downloader_sdk::SourceItem;
+    use source_downloader_sdk::storage::{
+        ItemContentLite, ProcessingContent, ProcessingStatus, ProcessingStorage,
+        ProcessingTargetPath, ProcessorRun, ProcessorRunQuery, RunOutcome,
+    };
+    use source_downloader_sdk::time::OffsetDateTime;
+
+    #[tokio::test]
+    async fn test_processor_runs() {
+        let s = MemoryProcessingStorage::new();
+        let run = |processor_name: &str| ProcessorRun {
+            id: None,
+            trace_id: "1".to_string(),
+            processor_name: processor_name.to_string(),
+            trigger_source: "trigger".to_string(),
+            started_at: OffsetDateTime::now_utc(),
+            ended_at: OffsetDateTime::now_utc(),
+            fetched_count: 0,
+            processed_count: 0,
+            filtered_count: 0,
+            fetch_took_ms: None,
+            outcome: RunOutcome::Success,
+            error: None,
+        };
+        let first = s.save_processor_run(&run("a")).await.unwrap();
+        s.save_processor_run(&run("b")).await.unwrap();
+        let third = s.save_processor_run(&run("a")).await.unwrap();
+
+        let mut query = ProcessorRunQuery {
+            processor_name: "a".to_string(),
+            max_id: None,
+            limit: 10,
+        };
+        let runs = s.query_processor_runs(&query).await.unwrap();
+        assert_eq!(
+            runs.iter().map(|x| x.id.unwrap()).collect::<Vec<_>>(),
+            vec![third, first]
+        );
+        query.max_id = Some(third);
+        let runs = s.query_processor_runs(&query).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, Some(first));
+    }
+
+    #[tokio::test]
+    async fn test_find_content_by_target_path() {
+        let s = MemoryProcessingStorage::new();
+        let content = || ProcessingContent {
+            id: None,
+            processor_name: "a".to_string(),
+            item_hash: "hash".to_string(),
+            item_identity: None,
+            item_content: ItemContentLite {
+                source_item: SourceItem {
+                    title: "a".to_string(),
+                    link: "https://example.com".parse().unwrap(),
+                    datetime: OffsetDateTime::now_utc(),
+                    content_type: "text/html".to_string(),
+                    download_uri: "https://example.com/download".parse().unwrap(),
+                    attrs: Default::default(),
+                    tags: Default::default(),
+                    identity: None,
+                },
+                item_variables: Default::default(),
+            },
+            rename_times: 0,
+            status: ProcessingStatus::Renamed,
+            failure_reason: None,
+            created_at: OffsetDateTime::now_utc(),
+            updated_at: None,
+        };
+        let path = "/mnt/target/S01E01.mkv".to_string();
+        let before = s.save_processing_content(&content()).await.unwrap();
+        s.save_file_targets(before, vec![path.clone()])
+            .await
+            .unwrap();
+        let res = s.find_content_by_target_path(&path).await.unwrap().unwrap();
+        assert_eq!(res.id, Some(before));
+
+        // 被新的content接管
+        let after = s.save_processing_content(&content()).await.unwrap();
+        s.save_file_targets(after, vec![path.clone()])
+            .await
+            .unwrap();
+        let res = s.find_content_by_target_path(&path).await.unwrap().unwrap();
+        assert_eq!(res.id, Some(after));
+        assert!(
+            s.find_content_by_target_path("/mnt/target/S01E02.mkv")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // 已被接管的路径不会被之前的content删除
+        s.delete_file_targets(before, std::slice::from_ref(&path))
+            .await
+            .unwrap();
+        assert!(
+            s.find_content_by_target_path(&path)
+                .await
+                .unwrap()
+                .is_some()
+        );
+        s.delete_file_targets(after, std::slice::from_ref(&path))
+            .await
+            .unwrap();
+        assert!(
+            s.find_content_by_target_path(&path)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_target_paths() {
+        let s = MemoryProcessingStorage::new();
+        let path = |p: &str, hash: &str| ProcessingTargetPath {
+            path: p.to_string(),
+            processor_name: "a".to_string(),
+            item_hash: hash.to_string(),
+        };
+        let occupied = s
+            .reserve_paths(vec![path("/mnt/a.mkv", "a"), path("/mnt/b.mkv", "a")])
+            .await
+            .unwrap();
+        assert!(occupied.is_empty());
+        let query = vec!["/mnt/a.mkv".to_string(), "/mnt/c.mkv".to_string()];
+        assert_eq!(s.find_paths(&query).await.unwrap().len(), 1);
+
+        // 有冲突时不占用任何路径
+        let occupied = s
+            .reserve_paths(vec![path("/mnt/a.mkv", "b"), path("/mnt/c.mkv", "b")])
+            .await
+            .unwrap();
+        assert_eq!(occupied[0].item_hash, "a");
+        assert_eq!(s.find_paths(&query).await.unwrap().len(), 1);
+
+        s.delete_paths(&["/mnt/b.mkv".to_string()]).await.unwrap();
+        assert!(
+            s.find_paths(&["/mnt/b.mkv".to_string()])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        s.delete_paths_by_item("a", "a").await.unwrap();
+        assert!(s.find_paths(&query).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS item_file_target
+(
+    -- target path of the renamed file
+    target_path CHARACTER VARYING PRIMARY KEY,
+    -- processing_content.id which owns the file
+    content_id  INTEGER NOT NULL
+);
+CREATE INDEX idx_contentid ON item_file_target (content_id);
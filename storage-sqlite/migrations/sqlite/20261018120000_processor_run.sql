@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS processor_run
+(
+    id              INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    trace_id        VARCHAR(64)                       NOT NULL,
+    processor_name  VARCHAR(255)                      NOT NULL,
+    -- trigger, manual, items
+    trigger_source  VARCHAR(32)                       NOT NULL,
+    started_at      DATETIME                          NOT NULL,
+    ended_at        DATETIME                          NOT NULL,
+    fetched_count   INT                               NOT NULL DEFAULT 0,
+    processed_count INT                               NOT NULL DEFAULT 0,
+    filtered_count  INT                               NOT NULL DEFAULT 0,
+    fetch_took_ms   INTEGER                                    DEFAULT NULL,
+    -- 0 success, 1 failure, 2 cancelled
+    outcome         INT                               NOT NULL,
+    error           TEXT
+);
+CREATE INDEX idx_processorrun_processorname_id ON processor_run (processor_name, id DESC);
@@ -8,9 +8,11 @@ use sea_orm::*;
 use serde_json::json;
 use source_downloader_sdk::storage::{
     Error, ProcessingContent, ProcessingContentQuery, ProcessingStatus, ProcessingStorage,
-    ProcessingTargetPath, ProcessorSourceState,
+    ProcessingTargetPath, ProcessorRun, ProcessorRunQuery, ProcessorSourceState, RunOutcome,
 };
 use std::str::FromStr;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
 
 pub struct SeaProcessingStorage {
     db: DatabaseConnection,
@@ -72,8 +74,29 @@ impl SeaProcessingStorage {
             processor_name: saved.processor_name,
             source_id: saved.source_id,
             last_pointer: saved.last_pointer_json,
+            retry_times: u32::try_from(saved.retry_times).unwrap_or_default(),
+            last_active_at: saved
+                .last_active_at
+                .and_then(|x| OffsetDateTime::parse(&x, &Rfc3339).ok()),
         })
     }
+
+    fn model_to_processor_run(saved: processor_run::Model) -> ProcessorRun {
+        ProcessorRun {
+            id: Some(saved.id),
+            trace_id: saved.trace_id,
+            processor_name: saved.processor_name,
+            trigger_source: saved.trigger_source,
+            started_at: saved.started_at,
+            ended_at: saved.ended_at,
+            fetched_count: saved.fetched_count,
+            processed_count: saved.processed_count,
+            filtered_count: saved.filtered_count,
+            fetch_took_ms: saved.fetch_took_ms.map(|x| x as u64),
+            outcome: RunOutcome::from(saved.outcome),
+            error: saved.error,
+        }
+    }
 }
 
 #[allow(dead_code, unused)]
@@ -135,7 +158,21 @@ impl ProcessingStorage for SeaProcessingStorage {
         processor_name: &str,
         item_hash: &str,
     ) -> Result<Option<ProcessingContent>, Error> {
-        todo!()
+        let model = processing_record::Entity::find()
+            .filter(
+                processing_record::Column::ProcessorName
+                    .eq(processor_name)
+                    .and(processing_record::Column::ItemHash.eq(item_hash)),
+            )
+            .one(&self.db)
+            .await
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })?;
+        match model {
+            None => Ok(None),
+            Some(model) => Ok(Some(Self::model_to_content(model)?)),
+        }
     }
 
     async fn find_content_by_id(&self, id: i64) -> Result<Option<ProcessingContent>, Error> {
@@ -257,6 +294,59 @@ impl ProcessingStorage for SeaProcessingStorage {
         }
     }
 
+    async fn save_file_targets(&self, content_id: i64, paths: Vec<String>) -> Result<(), Error> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let models = paths.into_iter().map(|path| item_file_target::ActiveModel {
+            target_path: Set(path),
+            content_id: Set(content_id),
+        });
+        item_file_target::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(item_file_target::Column::TargetPath)
+                    .update_column(item_file_target::Column::ContentId)
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })
+    }
+
+    async fn delete_file_targets(&self, content_id: i64, paths: &[String]) -> Result<(), Error> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        item_file_target::Entity::delete_many()
+            .filter(item_file_target::Column::ContentId.eq(content_id))
+            .filter(item_file_target::Column::TargetPath.is_in(paths.iter().cloned()))
+            .exec(&self.db)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })
+    }
+
+    async fn find_content_by_target_path(
+        &self,
+        path: &str,
+    ) -> Result<Option<ProcessingContent>, Error> {
+        let target = item_file_target::Entity::find_by_id(path.to_owned())
+            .one(&self.db)
+            .await
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })?;
+        match target {
+            None => Ok(None),
+            Some(target) => self.find_content_by_id(target.content_id).await,
+        }
+    }
+
     async fn find_processor_source_state(
         &self,
         processor_name: &str,
@@ -294,8 +384,12 @@ impl ProcessingStorage for SeaProcessingStorage {
             processor_name: Set(state.processor_name.to_owned()),
             source_id: Set(state.source_id.to_owned()),
             last_pointer_json: Set(state.last_pointer.clone()),
-            retry_times: Set(0),
-            last_active_at: Set(Some(time::OffsetDateTime::now_utc().to_string())),
+            retry_times: Set(state.retry_times as i32),
+            last_active_at: Set(state
+                .last_active_at
+                .unwrap_or_else(OffsetDateTime::now_utc)
+                .format(&Rfc3339)
+                .ok()),
         };
 
         let saved = model
@@ -310,14 +404,198 @@ impl ProcessingStorage for SeaProcessingStorage {
     }
 
     async fn save_paths(&self, paths: Vec<ProcessingTargetPath>) -> Result<(), Error> {
-        todo!()
+        if paths.is_empty() {
+            return Ok(());
+        }
+        let now = time::OffsetDateTime::now_utc();
+        let models = paths.into_iter().map(|path| target_path::ActiveModel {
+            id: Set(path.path),
+            processor_name: Set(path.processor_name),
+            item_hash: Set(path.item_hash),
+            created_at: Set(now),
+        });
+        target_path::Entity::insert_many(models)
+            .on_conflict(
+                OnConflict::column(target_path::Column::Id)
+                    .update_columns([
+                        target_path::Column::ProcessorName,
+                        target_path::Column::ItemHash,
+                        target_path::Column::CreatedAt,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })
+    }
+
+    async fn reserve_paths(
+        &self,
+        paths: Vec<ProcessingTargetPath>,
+    ) -> Result<Vec<ProcessingTargetPath>, Error> {
+        if paths.is_empty() {
+            return Ok(vec![]);
+        }
+        let to_error = |e: DbErr| Error {
+            message: e.to_string(),
+        };
+        // 先插入再查询, 插入时持有写锁, 其他连接无法在查询和提交之间占用路径
+        let txn = self.db.begin().await.map_err(to_error)?;
+        let now = time::OffsetDateTime::now_utc();
+        let models = paths.iter().map(|path| target_path::ActiveModel {
+            id: Set(path.path.clone()),
+            processor_name: Set(path.processor_name.clone()),
+            item_hash: Set(path.item_hash.clone()),
+            created_at: Set(now),
+        });
+        target_path::Entity::insert_many(models)
+            .on_conflict_do_nothing()
+            .exec(&txn)
+            .await
+            .map_err(to_error)?;
+        let occupied = target_path::Entity::find()
+            .filter(target_path::Column::Id.is_in(paths.iter().map(|x| x.path.clone())))
+            .all(&txn)
+            .await
+            .map_err(to_error)?
+            .into_iter()
+            .filter(|x| {
+                paths.iter().any(|p| {
+                    p.path == x.id
+                        && (p.processor_name != x.processor_name || p.item_hash != x.item_hash)
+                })
+            })
+            .map(|x| ProcessingTargetPath {
+                path: x.id,
+                processor_name: x.processor_name,
+                item_hash: x.item_hash,
+            })
+            .collect::<Vec<_>>();
+        if occupied.is_empty() {
+            txn.commit().await.map_err(to_error)?;
+        } else {
+            txn.rollback().await.map_err(to_error)?;
+        }
+        Ok(occupied)
+    }
+
+    async fn find_paths(&self, paths: &[String]) -> Result<Vec<ProcessingTargetPath>, Error> {
+        if paths.is_empty() {
+            return Ok(vec![]);
+        }
+        let models = target_path::Entity::find()
+            .filter(target_path::Column::Id.is_in(paths.iter().cloned()))
+            .all(&self.db)
+            .await
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })?;
+        Ok(models
+            .into_iter()
+            .map(|x| ProcessingTargetPath {
+                path: x.id,
+                processor_name: x.processor_name,
+                item_hash: x.item_hash,
+            })
+            .collect())
+    }
+
+    async fn delete_paths(&self, paths: &[String]) -> Result<(), Error> {
+        if paths.is_empty() {
+            return Ok(());
+        }
+        target_path::Entity::delete_many()
+            .filter(target_path::Column::Id.is_in(paths.iter().cloned()))
+            .exec(&self.db)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })
+    }
+
+    async fn delete_paths_by_item(
+        &self,
+        processor_name: &str,
+        item_hash: &str,
+    ) -> Result<(), Error> {
+        target_path::Entity::delete_many()
+            .filter(
+                target_path::Column::ProcessorName
+                    .eq(processor_name)
+                    .and(target_path::Column::ItemHash.eq(item_hash)),
+            )
+            .exec(&self.db)
+            .await
+            .map(|_| ())
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })
+    }
+
+    async fn save_processor_run(&self, run: &ProcessorRun) -> Result<i64, Error> {
+        let model = processor_run::ActiveModel {
+            id: if let Some(id) = run.id {
+                Set(id)
+            } else {
+                NotSet
+            },
+            trace_id: Set(run.trace_id.to_owned()),
+            processor_name: Set(run.processor_name.to_owned()),
+            trigger_source: Set(run.trigger_source.to_owned()),
+            started_at: Set(run.started_at),
+            ended_at: Set(run.ended_at),
+            fetched_count: Set(run.fetched_count),
+            processed_count: Set(run.processed_count),
+            filtered_count: Set(run.filtered_count),
+            fetch_took_ms: Set(run.fetch_took_ms.map(|x| x as i64)),
+            outcome: Set(run.outcome as i32),
+            error: Set(run.error.to_owned()),
+        };
+        model
+            .save(&self.db)
+            .await
+            .map(|x| x.id.unwrap())
+            .map_err(|x| Error {
+                message: x.to_string(),
+            })
+    }
+
+    async fn query_processor_runs(
+        &self,
+        query: &ProcessorRunQuery,
+    ) -> Result<Vec<ProcessorRun>, Error> {
+        let mut db_query = processor_run::Entity::find()
+            .filter(processor_run::Column::ProcessorName.eq(&query.processor_name));
+        if let Some(max_id) = query.max_id {
+            db_query = db_query.filter(processor_run::Column::Id.lt(max_id));
+        }
+        let models = db_query
+            .order_by_desc(processor_run::Column::Id)
+            .limit(query.limit)
+            .all(&self.db)
+            .await
+            .map_err(|e| Error {
+                message: e.to_string(),
+            })?;
+        Ok(models
+            .into_iter()
+            .map(Self::model_to_processor_run)
+            .collect())
     }
 }
 
 #[cfg(test)]
 mod test {
     use crate::SeaProcessingStorage;
-    use source_downloader_sdk::storage::{ItemContentLite, ProcessingContent, ProcessingStatus, ProcessingStorage};
+    use serde_json::json;
+    use source_downloader_sdk::storage::{
+        ItemContentLite, ProcessingContent, ProcessingStatus, ProcessingStorage,
+        ProcessingTargetPath, ProcessorRun, ProcessorRunQuery, ProcessorSourceState, RunOutcome,
+    };
     use source_downloader_sdk::SourceItem;
     use std::collections::HashMap;
     use time::OffsetDateTime;
@@ -407,6 +685,208 @@ mod test {
         assert_eq!(res.failure_reason, Some("Download failed".to_string()));
         assert_eq!(res.status, ProcessingStatus::Failure);
     }
+
+    #[tokio::test]
+    async fn test_find_by_name_and_hash() {
+        let db_url = "sqlite::memory:";
+        let s = SeaProcessingStorage::new(db_url).await.unwrap();
+
+        let content = create_test_processing_content("test_processor_6", ProcessingStatus::Renamed);
+        let id = s.save_processing_content(&content).await.unwrap();
+        let res = s
+            .find_by_name_and_hash("test_processor_6", &content.item_hash)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(res.id, Some(id));
+        assert!(
+            s.find_by_name_and_hash("test_processor_7", &content.item_hash)
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_content_by_target_path() {
+        let db_url = "sqlite::memory:";
+        let s = SeaProcessingStorage::new(db_url).await.unwrap();
+
+        let before = create_test_processing_content("test_processor_4", ProcessingStatus::Renamed);
+        let before_id = s.save_processing_content(&before).await.unwrap();
+        let path = "/mnt/target/S01E01.mkv".to_string();
+        s.save_file_targets(before_id, vec![path.clone()])
+            .await
+            .unwrap();
+        let res = s.find_content_by_target_path(&path).await.unwrap().unwrap();
+        assert_eq!(res.id, Some(before_id));
+
+        // 被新的content接管
+        let after = create_test_processing_content("test_processor_4", ProcessingStatus::Renamed);
+        let after_id = s.save_processing_content(&after).await.unwrap();
+        s.save_file_targets(after_id, vec![path.clone()])
+            .await
+            .unwrap();
+        let res = s.find_content_by_target_path(&path).await.unwrap().unwrap();
+        assert_eq!(res.id, Some(after_id));
+        assert!(
+            s.find_content_by_target_path("/mnt/target/S01E02.mkv")
+                .await
+                .unwrap()
+                .is_none()
+        );
+
+        // 已被接管的路径不会被之前的content删除
+        s.delete_file_targets(before_id, std::slice::from_ref(&path))
+            .await
+            .unwrap();
+        assert!(s.find_content_by_target_path(&path).await.unwrap().is_some());
+        s.delete_file_targets(after_id, std::slice::from_ref(&path))
+            .await
+            .unwrap();
+        assert!(s.find_content_by_target_path(&path).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_processor_runs() {
+        let db_url = "sqlite::memory:";
+        let s = SeaProcessingStorage::new(db_url).await.unwrap();
+
+        let run = |outcome: RunOutcome| ProcessorRun {
+            id: None,
+            trace_id: Uuid::new_v4().to_string(),
+            processor_name: "test_processor_8".to_string(),
+            trigger_source: "manual".to_string(),
+            started_at: OffsetDateTime::now_utc(),
+            ended_at: OffsetDateTime::now_utc(),
+            fetched_count: 3,
+            processed_count: 2,
+            filtered_count: 1,
+            fetch_took_ms: Some(10),
+            outcome,
+            error: None,
+        };
+        let first = s.save_processor_run(&run(RunOutcome::Success)).await.unwrap();
+        let second = s.save_processor_run(&run(RunOutcome::Failure)).await.unwrap();
+
+        let mut query = ProcessorRunQuery {
+            processor_name: "test_processor_8".to_string(),
+            max_id: None,
+            limit: 1,
+        };
+        let runs = s.query_processor_runs(&query).await.unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].id, Some(second));
+        assert_eq!(runs[0].outcome, RunOutcome::Failure);
+        assert_eq!(runs[0].fetch_took_ms, Some(10));
+
+        query.max_id = Some(second);
+        let runs = s.query_processor_runs(&query).await.unwrap();
+        assert_eq!(runs[0].id, Some(first));
+        assert_eq!(runs[0].fetched_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_processor_source_state() {
+        let db_url = "sqlite::memory:";
+        let s = SeaProcessingStorage::new(db_url).await.unwrap();
+
+        let mut state = ProcessorSourceState {
+            id: None,
+            processor_name: "test_processor_9".to_string(),
+            source_id: "mock".to_string(),
+            last_pointer: json!({"page": 1}),
+            retry_times: 2,
+            last_active_at: None,
+        };
+        let saved = s.save_processor_source_state(&state).await.unwrap();
+        assert!(saved.id.is_some());
+        assert!(saved.last_active_at.is_some());
+
+        state.id = saved.id;
+        state.retry_times = 0;
+        s.save_processor_source_state(&state).await.unwrap();
+        let found = s
+            .find_processor_source_state("test_processor_9", "mock")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.id, saved.id);
+        assert_eq!(found.retry_times, 0);
+        assert_eq!(found.last_pointer, json!({"page": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_target_paths() {
+        let db_url = "sqlite::memory:";
+        let s = SeaProcessingStorage::new(db_url).await.unwrap();
+
+        let path = |p: &str, hash: &str| ProcessingTargetPath {
+            path: p.to_string(),
+            processor_name: "test_processor_5".to_string(),
+            item_hash: hash.to_string(),
+        };
+        s.save_paths(vec![path("/mnt/a.mkv", "a"), path("/mnt/b.mkv", "b")])
+            .await
+            .unwrap();
+        let query = vec!["/mnt/a.mkv".to_string(), "/mnt/c.mkv".to_string()];
+        let found = s.find_paths(&query).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item_hash, "a");
+
+        // 被其他Item占用
+        s.save_paths(vec![path("/mnt/a.mkv", "c")]).await.unwrap();
+        let found = s.find_paths(&query).await.unwrap();
+        assert_eq!(found[0].item_hash, "c");
+
+        s.delete_paths_by_item("test_processor_5", "c").await.unwrap();
+        assert!(s.find_paths(&query).await.unwrap().is_empty());
+
+        s.delete_paths(&["/mnt/b.mkv".to_string()]).await.unwrap();
+        assert!(
+            s.find_paths(&["/mnt/b.mkv".to_string()])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reserve_paths() {
+        let db_url = "sqlite::memory:";
+        let s = SeaProcessingStorage::new(db_url).await.unwrap();
+
+        let path = |p: &str, hash: &str| ProcessingTargetPath {
+            path: p.to_string(),
+            processor_name: "test_processor_10".to_string(),
+            item_hash: hash.to_string(),
+        };
+        let occupied = s
+            .reserve_paths(vec![path("/mnt/a.mkv", "a")])
+            .await
+            .unwrap();
+        assert!(occupied.is_empty());
+        // 同一个Item重复占用
+        let occupied = s
+            .reserve_paths(vec![path("/mnt/a.mkv", "a"), path("/mnt/b.mkv", "a")])
+            .await
+            .unwrap();
+        assert!(occupied.is_empty());
+
+        // 有冲突时不占用任何路径
+        let occupied = s
+            .reserve_paths(vec![path("/mnt/a.mkv", "b"), path("/mnt/c.mkv", "b")])
+            .await
+            .unwrap();
+        assert_eq!(occupied.len(), 1);
+        assert_eq!(occupied[0].item_hash, "a");
+        assert!(
+            s.find_paths(&["/mnt/c.mkv".to_string()])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
 }
 
 mod processing_record {
@@ -466,3 +946,62 @@ mod item_file_content {
 
     impl ActiveModelBehavior for ActiveModel {}
 }
+
+mod item_file_target {
+    use sea_orm::entity::prelude::*;
+
+    #[sea_orm::model]
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "item_file_target")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub target_path: String,
+        pub content_id: i64,
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+mod target_path {
+    use sea_orm::entity::prelude::*;
+    use time::OffsetDateTime;
+
+    #[sea_orm::model]
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "target_path")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub id: String,
+        pub processor_name: String,
+        pub item_hash: String,
+        pub created_at: OffsetDateTime,
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+mod processor_run {
+    use sea_orm::entity::prelude::*;
+    use time::OffsetDateTime;
+
+    #[sea_orm::model]
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "processor_run")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = true)]
+        pub id: i64,
+        pub trace_id: String,
+        pub processor_name: String,
+        pub trigger_source: String,
+        pub started_at: OffsetDateTime,
+        pub ended_at: OffsetDateTime,
+        pub fetched_count: u32,
+        pub processed_count: u32,
+        pub filtered_count: u32,
+        pub fetch_took_ms: Option<i64>,
+        pub outcome: i32,
+        pub error: Option<String>,
+    }
+
+    impl ActiveModelBehavior for ActiveModel {}
+}